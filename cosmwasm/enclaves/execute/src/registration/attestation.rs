@@ -2,9 +2,14 @@
 use core::mem;
 
 use enclave_crypto::KeyPair;
+use enclave_ffi_types::NodeAuthResult;
+use lazy_static::lazy_static;
+use std::sync::SgxMutex;
 use std::vec::Vec;
 
-#[cfg(feature = "SGX_MODE_HW")]
+#[cfg(feature = "test")]
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
 use log::*;
 
 #[cfg(feature = "SGX_MODE_HW")]
@@ -27,11 +32,13 @@ use sgx_types::{
     sgx_tvl_verify_qve_report_and_identity,
 };
 
-use sgx_types::{sgx_ql_qv_result_t, sgx_quote_sign_type_t, sgx_report_body_t, sgx_status_t};
+use sgx_types::{
+    sgx_ql_qv_result_t, sgx_quote_sign_type_t, sgx_report_body_t, sgx_report_t, sgx_status_t,
+};
 
 #[cfg(feature = "SGX_MODE_HW")]
 use sgx_types::{
-    c_int, sgx_epid_group_id_t, sgx_quote_nonce_t, sgx_report_data_t, sgx_report_t, sgx_spid_t,
+    c_int, sgx_epid_group_id_t, sgx_quote_nonce_t, sgx_report_data_t, sgx_spid_t,
     sgx_target_info_t, SgxResult,
 };
 
@@ -280,6 +287,117 @@ pub fn get_mr_enclave() -> [u8; 32] {
     rsgx_self_report().body.mr_enclave.m
 }
 
+/// Makes the self report fail, so tests can exercise the error path of
+/// verification
+#[cfg(feature = "test")]
+pub static FAIL_SELF_TARGET_INFO: AtomicBool = AtomicBool::new(false);
+
+/// Number of times the report of this enclave was created
+#[cfg(feature = "test")]
+pub static SELF_REPORT_CREATIONS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref SELF_REPORT: SgxMutex<Option<sgx_report_t>> = SgxMutex::new(None);
+}
+
+#[cfg(feature = "mock")]
+lazy_static! {
+    static ref MOCK_SELF_REPORT: SgxMutex<sgx_report_t> = SgxMutex::new(sgx_report_t::default());
+}
+
+/// Use `report` as the report of this enclave, so verification can be
+/// exercised against any measurement without SGX hardware. Only applies
+/// outside of `SGX_MODE_HW`, where the report comes from the hardware.
+#[cfg(feature = "mock")]
+pub fn set_mock_report(report: sgx_report_t) {
+    if let Ok(mut mock) = MOCK_SELF_REPORT.lock() {
+        *mock = report;
+    }
+    clear_cached_report();
+}
+
+#[cfg(feature = "test")]
+fn self_target_info_failure() -> Result<(), sgx_status_t> {
+    if FAIL_SELF_TARGET_INFO.load(Ordering::SeqCst) {
+        return Err(sgx_status_t::SGX_ERROR_UNEXPECTED);
+    }
+
+    Ok(())
+}
+
+/// Report of the enclave running this code, used as the reference when
+/// verifying reports of other nodes
+#[cfg(not(feature = "SGX_MODE_HW"))]
+pub fn get_report() -> Result<sgx_report_t, NodeAuthResult> {
+    #[cfg(feature = "test")]
+    self_target_info_failure().map_err(|_| NodeAuthResult::InvalidSelfReport)?;
+    #[cfg(feature = "test")]
+    SELF_REPORT_CREATIONS.fetch_add(1, Ordering::SeqCst);
+
+    #[cfg(feature = "mock")]
+    return MOCK_SELF_REPORT
+        .lock()
+        .map(|report| *report)
+        .map_err(|_| NodeAuthResult::InvalidSelfReport);
+
+    #[cfg(not(feature = "mock"))]
+    Ok(sgx_report_t::default())
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+pub fn get_report() -> Result<sgx_report_t, NodeAuthResult> {
+    #[cfg(feature = "test")]
+    SELF_REPORT_CREATIONS.fetch_add(1, Ordering::SeqCst);
+
+    let target_info = self_target_info().map_err(|e| {
+        error!("Failed to get the target info of this enclave: {:?}", e);
+        NodeAuthResult::InvalidSelfReport
+    })?;
+
+    rsgx_create_report(&target_info, &sgx_report_data_t::default()).map_err(|e| {
+        error!("Failed to create the report of this enclave: {:?}", e);
+        NodeAuthResult::InvalidSelfReport
+    })
+}
+
+/// Same as `get_report`, but the report is only created once, since it
+/// doesn't change while the enclave runs. Failures aren't cached, so they can
+/// be retried.
+pub fn get_cached_report() -> Result<sgx_report_t, NodeAuthResult> {
+    let mut cached = SELF_REPORT.lock().map_err(|_| {
+        error!("Self report cache is poisoned");
+        NodeAuthResult::InvalidSelfReport
+    })?;
+
+    if let Some(report) = *cached {
+        return Ok(report);
+    }
+
+    let report = get_report()?;
+    *cached = Some(report);
+    Ok(report)
+}
+
+/// Forget the cached self report, so the next verification creates it again
+#[cfg(any(feature = "test", feature = "mock"))]
+pub fn clear_cached_report() {
+    if let Ok(mut cached) = SELF_REPORT.lock() {
+        *cached = None;
+    }
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+fn self_target_info() -> SgxResult<sgx_target_info_t> {
+    #[cfg(feature = "test")]
+    self_target_info_failure()?;
+
+    let mut target_info = sgx_target_info_t::default();
+    match unsafe { sgx_self_target(&mut target_info) } {
+        sgx_status_t::SGX_SUCCESS => Ok(target_info),
+        e => Err(e),
+    }
+}
+
 #[cfg(not(feature = "SGX_MODE_HW"))]
 pub fn verify_quote_ecdsa(
     _vec_quote: &[u8],
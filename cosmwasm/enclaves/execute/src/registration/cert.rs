@@ -370,7 +370,11 @@ pub fn verify_ra_cert(
         }
     }
 
-    let report_public_key = report.sgx_quote_body.isv_enclave_report.report_data[0..32].to_vec();
+    let report_public_key = report
+        .sgx_quote_body
+        .isv_enclave_report
+        .get_owner_key_unchecked()
+        .to_vec();
     Ok(report_public_key)
 }
 
@@ -392,7 +396,7 @@ pub fn verify_quote_status(
     //     transform_u32_to_array_of_u8(report.sgx_quote_body.gid)
     // );
     #[cfg(not(feature = "epid_whitelist_disabled"))]
-    if !check_epid_gid_is_whitelisted(&report.sgx_quote_body.gid) {
+    if !check_epid_gid_is_whitelisted(&report.sgx_quote_body.gid.as_u32()) {
         error!(
             "Platform verification error: quote status {:?}",
             &report.sgx_quote_body.gid
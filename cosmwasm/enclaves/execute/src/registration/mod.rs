@@ -8,7 +8,7 @@ mod hex;
 mod offchain;
 mod onchain;
 mod persistency;
-mod report;
+pub mod report;
 mod seed_exchange;
 
 #[cfg(feature = "SGX_MODE_HW")]
@@ -20,6 +20,24 @@ pub mod print_report;
 pub mod check_patch_level;
 pub mod seed_service;
 
+/// Flat re-export of the commonly used attestation types, so consumers don't
+/// depend on the internal layout of the registration modules. The rest of the
+/// verification API is in `registration::report`.
+///
+/// ```ignore
+/// // ignored: this crate is an SGX enclave, which doc tests can't build. The
+/// // imports are checked by report::tests::test_prelude instead
+/// use secret_enclave::registration::prelude::{
+///     AdvisoryIDs, AttestationReport, NodeAuthResult, SgxEnclaveReport, SgxQuote, SgxQuoteStatus,
+/// };
+/// ```
+pub mod prelude {
+    pub use super::report::{
+        AdvisoryIDs, AttestationReport, SgxEnclaveReport, SgxQuote, SgxQuoteStatus,
+    };
+    pub use enclave_ffi_types::NodeAuthResult;
+}
+
 #[cfg(feature = "test")]
 pub mod tests {
     use super::*;
@@ -31,10 +49,96 @@ pub mod tests {
 
         count_failures!(failures, {
             report::tests::test_sgx_quote_parse_from();
+            report::tests::test_sgx_report_key_id_match();
+            report::tests::test_sgx_report_key_id_mismatch();
+            report::tests::test_sgx_report_key_id_policy();
+            report::tests::test_cpu_svn_missing_updates();
+            report::tests::test_enclave_report_bad_size();
+            report::tests::test_verify_threshold_met();
+            report::tests::test_verify_threshold_not_met();
+            report::tests::test_decode_quote_body_padding();
+            report::tests::test_verify_with_hook();
+            report::tests::test_verify_batch_duplicate_key();
+            report::tests::test_verify_transcript();
+            report::tests::test_sgx_quote_status_policy();
+            report::tests::test_measurement_matchers();
+            report::tests::test_sgx_quote_parse_v3();
+            report::tests::test_verify_with_hook_deadline();
+            report::tests::test_fleet_tcb_summary();
+            report::tests::test_verify_64bit();
+            report::tests::test_advisory_whitelist_layered();
+            report::tests::test_verify_detailed_permissive();
+            report::tests::test_enclave_report_extension();
+            report::tests::test_verify_svn_consistency();
+            report::tests::test_verify_into();
+            report::tests::test_verification_available();
+            report::tests::test_verify_report_support_json();
+            report::tests::test_report_attributes_flags();
+            report::tests::test_get_owner_key();
+            report::tests::test_enclave_report_kss();
+            report::tests::test_verify_with_min_svn();
+            report::tests::test_ct_eq();
+            report::tests::test_report_parse_errors();
+            report::tests::test_enclave_report_display();
+            report::tests::test_verify_signer();
+            report::tests::test_report_data_fields();
+            report::tests::test_verify_self_report_failure();
+            report::tests::test_attestation_report_config_and_sw_hardening();
+            report::tests::test_attestation_report_advisories();
+            report::tests::test_sgx_report_size();
+            report::tests::test_enclave_report_builder();
+            report::tests::test_enclave_report_to_bytes();
+            report::tests::test_enclave_report_parse_strict();
+            report::tests::test_get_cached_report();
+            report::tests::test_verify_with_min_cpu_svn();
+            report::tests::test_epid_quote_sig_type();
+            report::tests::test_verify_prod_id();
+            report::tests::test_verify_policy_fields();
+            report::tests::test_attribute_flag_set();
+            report::tests::test_xfrm_features();
+            report::tests::test_attestation_report_v5();
+            report::tests::test_attestation_report_raw_quote_body();
+            report::tests::test_attestation_report_max_age();
+            report::tests::test_verify_report_data_contains();
+            report::tests::test_enclave_report_eq();
+            report::tests::test_enclave_report_clone();
+            report::tests::test_epid_group_id();
+            report::tests::test_verify_qe_identity();
+            report::tests::test_advisory_ids_from_ias_report();
+            report::tests::test_verify_against_measurements();
+            report::tests::test_enclave_report_fingerprint();
+            report::tests::test_advisory_ids_from_str();
+            report::tests::test_verify_not_debug();
+            report::tests::test_enclave_report_ref();
+            report::tests::test_sgx_quote_parse_v1();
+            report::tests::test_report_data_is_empty();
+            report::tests::test_node_auth_result_codes();
+            report::tests::test_verify_batch_mixed();
+            report::tests::test_verify_same_product();
+            report::tests::test_take_bytes();
+            report::tests::test_get_keys();
+            report::tests::test_parse_from_quiet();
+            report::tests::test_parse_from_little_endian();
+            report::tests::test_same_platform_as();
+            report::tests::test_report_data_str();
+            report::tests::test_node_auth_result_to_result();
+            report::tests::test_set_owner_key();
+            report::tests::test_verify_attributes_masked();
+            report::tests::test_is_self_report();
+            report::tests::test_verify_misc_select();
+            report::tests::test_report_diff();
+            report::tests::test_sgx_quote_verify_qe_svn();
+            report::tests::test_parse_from_at();
+            report::tests::test_to_report_body();
+            report::tests::test_with_report_data();
+            report::tests::test_prelude();
+            report::tests::test_describe_advisory();
+            report::tests::test_vulnerable_detailed();
             report::tests::test_attestation_report_from_cert();
             report::tests::test_attestation_report_from_cert_invalid();
             report::tests::test_attestation_report_from_cert_api_version_not_compatible();
             report::tests::test_attestation_report_test();
+            report::tests::test_attestation_verify_full_dcap();
             report::tests::test_attestation_dcap();
             report::tests::test_attestation_dcap_temper();
             cert::tests::test_certificate_valid();
@@ -45,6 +149,39 @@ pub mod tests {
             panic!("{}: {} tests failed", file!(), failures);
         }
 
+        #[cfg(not(all(feature = "SGX_MODE_HW", feature = "production")))]
+        count_failures!(failures, {
+            report::tests::test_attestation_verify_full();
+            report::tests::test_attestation_verify_full_deadline();
+        });
+
+        #[cfg(feature = "report-serde")]
+        count_failures!(failures, {
+            report::tests::test_enclave_report_serde();
+        });
+
+        #[cfg(all(feature = "mock", not(feature = "SGX_MODE_HW")))]
+        count_failures!(failures, {
+            report::tests::test_verify_mock_report_match();
+            report::tests::test_verify_mock_report_mismatch();
+        });
+
+        #[cfg(feature = "SGX_MODE_HW")]
+        count_failures!(failures, {
+            report::tests::test_normalize_advisory_id();
+            report::tests::test_attestation_summary();
+            report::tests::test_check_acceptable();
+            report::tests::test_is_vulnerable();
+            report::tests::test_vulnerable_against();
+            report::tests::test_vulnerable_against_prefix();
+            report::tests::test_vulnerable_deduplicated();
+        });
+
+        #[cfg(all(feature = "SGX_MODE_HW", not(feature = "production")))]
+        count_failures!(failures, {
+            report::tests::test_dev_only_advisories();
+        });
+
         #[cfg(not(feature = "epid_whitelist_disabled"))]
         count_failures!(failures, {
             cert::tests::test_epid_whitelist();
@@ -8,27 +8,82 @@
 //! https://api.trustedservices.intel.com/documents/sgx-attestation-api-spec.pdf
 
 use std::array::TryFromSliceError;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::convert::TryFrom;
+use std::time::Instant;
 
 use log::*;
 
+use bitflags::bitflags;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use uuid::Uuid;
 
+use enclave_crypto::{sha_256, PUBLIC_KEY_SIZE};
 use enclave_ffi_types::NodeAuthResult;
 
+use super::attestation::{get_cached_report, verify_quote_ecdsa};
+#[cfg(feature = "SGX_MODE_HW")]
+use super::cert::verify_quote_status;
 use super::cert::{get_ias_auth_config, get_netscape_comment};
 
 #[cfg(feature = "test")]
 use sgx_types::sgx_quote_t;
+use sgx_types::{sgx_ql_qv_result_t, sgx_report_body_t};
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq)]
 pub enum Error {
     ReportParseError,
     ReportValidationError,
+    /// The input ended before the field being parsed; `expected` is the
+    /// length needed to parse it
+    ReportTruncated {
+        expected: usize,
+        got: usize,
+    },
+    /// Number of bytes left over after the last field
+    ReportTrailingBytes(usize),
+    /// A length-prefixed field doesn't fit what it has to contain
+    BadFieldLength {
+        field: &'static str,
+    },
+    /// A reserved byte of the report body is set, at `offset` in the body
+    NonZeroReserved {
+        offset: usize,
+    },
+    VerifyFailed {
+        stage: VerifyStage,
+        reason: NodeAuthResult,
+    },
+    EmptyOwnerKey,
+    /// The encryption key in the second half of `report_data` is all zeros
+    EmptyEncryptionKey,
+    /// The attestation report is older than the accepted maximum age
+    AttestationExpired,
+    /// The quote has a version this code doesn't know how to parse
+    UnsupportedQuoteVersion(u16),
+    /// Data to bind to the report doesn't fit in the 64 bytes of `report_data`
+    ReportDataTooLong(usize),
+}
+
+/// Stages of `AttestationReport::verify_full`, in the order they run
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerifyStage {
+    /// Extracting the report from the certificate, checking Intel's signature
+    /// over it and parsing it, or parsing a DCAP quote
+    Parse,
+    /// Verifying a DCAP quote against its collateral, which checks the
+    /// signature of the quote and the TCB status of the platform
+    Signature,
+    /// Quote status and advisories of the platform
+    QuoteStatus,
+    /// Age of the report
+    Freshness,
+    /// Measurement and attributes of the enclave
+    Measurement,
+    /// Public key bound into the report
+    Binding,
 }
 
 impl From<std::array::TryFromSliceError> for Error {
@@ -66,6 +121,29 @@ where
     serializer.serialize_str(&base64::encode(key))
 }
 
+#[cfg(feature = "report-serde")]
+fn as_hex<T, S>(bytes: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: AsRef<[u8]>,
+    S: Serializer,
+{
+    serializer.serialize_str(&hex::encode(bytes))
+}
+
+/// Decode hex into a byte array or vector, rejecting arrays of the wrong length
+#[cfg(feature = "report-serde")]
+fn from_hex<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: TryFrom<Vec<u8>>,
+    D: Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let bytes = hex::decode(String::deserialize(deserializer)?).map_err(D::Error::custom)?;
+    let len = bytes.len();
+    T::try_from(bytes).map_err(|_| D::Error::custom(format!("unexpected length {}", len)))
+}
+
 fn from_base64<'de, D>(deserializer: D) -> Result<Vec<u8>, D::Error>
 where
     D: Deserializer<'de>,
@@ -115,791 +193,5950 @@ static SUPPORTED_SIG_ALGS: SignatureAlgorithms = &[
 /// `SgxQuote`. The quote is then sent to some attestation service (IAS or
 /// DCAP-based AS). The endorsed `SgxQuote` is an attestation report signed by
 /// attestation service's private key, a.k.a., `EndorsedAttestationReport`.
+#[derive(Clone)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
 pub struct SgxEnclaveReport {
     /// Security version number of host system's CPU
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
     pub cpu_svn: [u8; 16],
     /// Misc select bits for the target enclave. Reserved for future function
     /// extension.
     pub misc_select: u32,
+    /// Extended product ID of the enclave, set when KSS is enabled
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
+    pub isv_ext_prod_id: [u8; 16],
     /// Attributes of the enclave, for example, whether the enclave is running
     /// in debug mode.
-    pub attributes: [u8; 16],
+    pub attributes: SgxReportAttributes,
     /// Measurement value of the enclave. See
     /// [`EnclaveMeasurement`](../types/struct.EnclaveMeasurement.html)
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
     pub mr_enclave: [u8; 32],
     /// Measurement value of the public key that verified the enclave. See
     /// [`EnclaveMeasurement`](../types/struct.EnclaveMeasurement.html)
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
     pub mr_signer: [u8; 32],
     /// Product ID of the enclave
     pub isv_prod_id: u16,
     /// Security version number of the enclave
     pub isv_svn: u16,
+    /// Configuration ID the enclave was loaded with, set when KSS is enabled
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
+    pub config_id: [u8; 64],
+    /// Configuration security version number, set when KSS is enabled
+    pub config_svn: u16,
+    /// Family ID of the enclave, set when KSS is enabled
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
+    pub isv_family_id: [u8; 16],
     /// Set of data used for communication between enclave and target enclave
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
     pub report_data: [u8; 64],
+    /// Bytes following the known fields, in case a newer layout extends the
+    /// report body
+    #[cfg_attr(
+        feature = "report-serde",
+        serde(serialize_with = "as_hex", deserialize_with = "from_hex")
+    )]
+    pub extra: Vec<u8>,
 }
 
+// The measurements and report_data are compared in constant time, and the
+// fields are combined without short-circuiting, so comparing a received report
+// against an expected one doesn't tell how much of it matched
+impl PartialEq for SgxEnclaveReport {
+    fn eq(&self, other: &Self) -> bool {
+        ct_eq(&self.mr_enclave, &other.mr_enclave)
+            & ct_eq(&self.mr_signer, &other.mr_signer)
+            & ct_eq(&self.report_data, &other.report_data)
+            & (self.cpu_svn == other.cpu_svn)
+            & (self.misc_select == other.misc_select)
+            & (self.isv_ext_prod_id == other.isv_ext_prod_id)
+            & (self.attributes == other.attributes)
+            & (self.isv_prod_id == other.isv_prod_id)
+            & (self.isv_svn == other.isv_svn)
+            & (self.config_id == other.config_id)
+            & (self.config_svn == other.config_svn)
+            & (self.isv_family_id == other.isv_family_id)
+            & (self.extra == other.extra)
+    }
+}
+
+impl Eq for SgxEnclaveReport {}
+
 impl std::fmt::Debug for SgxEnclaveReport {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         writeln!(f, "cpu_svn: {:?}", self.cpu_svn)?;
         writeln!(f, "misc_select: {:?}", self.misc_select)?;
+        writeln!(f, "isv_ext_prod_id: {:?}", self.isv_ext_prod_id)?;
         writeln!(f, "attributes: {:?}", self.attributes)?;
         writeln!(f, "mr_enclave: {:?}", self.mr_enclave)?;
         writeln!(f, "mr_signer: {:?}", self.mr_signer)?;
         writeln!(f, "isv_prod_id: {}", self.isv_prod_id)?;
         writeln!(f, "isv_svn: {}", self.isv_svn)?;
-        writeln!(f, "report_data: {:?}", &self.report_data.to_vec())
+        writeln!(f, "config_id: {:?}", &self.config_id.to_vec())?;
+        writeln!(f, "config_svn: {}", self.config_svn)?;
+        writeln!(f, "isv_family_id: {:?}", self.isv_family_id)?;
+        // report_data holds the node's public key, so only print all of it
+        // when explicitly asked for
+        #[cfg(feature = "full-debug")]
+        writeln!(f, "report_data: {:?}", &self.report_data.to_vec())?;
+        #[cfg(not(feature = "full-debug"))]
+        writeln!(f, "report_data: {:?}...", &self.report_data[..8])?;
+        writeln!(f, "extra: {:?}", &self.extra)
+    }
+}
+
+/// A field that differs between two reports, see `SgxEnclaveReport::diff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFieldDiff {
+    CpuSvn,
+    MiscSelect,
+    IsvExtProdId,
+    Attributes,
+    MrEnclave,
+    MrSigner,
+    IsvProdId,
+    IsvSvn,
+    ConfigId,
+    ConfigSvn,
+    IsvFamilyId,
+    ReportData,
+    Extra,
+}
+
+impl std::fmt::Display for SgxEnclaveReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "mr_enclave: {}..., isv_svn: {}, isv_prod_id: {}",
+            hex::encode(&self.mr_enclave[..8]),
+            self.isv_svn,
+            self.isv_prod_id
+        )
     }
 }
 
+/// Size in bytes of a serialized `SgxEnclaveReport` (`sgx_report_body_t`)
+pub const REPORT_BODY_LEN: usize = 384;
+
+/// Reserved regions of the report body, as (offset, length) within the body
+const REPORT_BODY_RESERVED: [(usize, usize); 4] = [(20, 12), (96, 32), (160, 32), (262, 42)];
+
+/// Size in bytes of a serialized `SgxReport` (`sgx_report_t`): the body, the
+/// key id and the MAC
+pub const SGX_REPORT_LEN: usize = REPORT_BODY_LEN + 32 + 16;
+
 impl SgxEnclaveReport {
-    /// Parse bytes of report into `SgxEnclaveReport`.
-    pub fn parse_from<'a>(bytes: &'a [u8]) -> Result<Self, Error> {
-        let mut pos: usize = 0;
-        let mut take = |n: usize| -> Result<&'a [u8], Error> {
-            if n > 0 && bytes.len() >= pos + n {
-                let ret = &bytes[pos..pos + n];
-                pos += n;
-                Ok(ret)
-            } else {
-                error!("Enclave report parsing error - bad report size");
-                Err(Error::ReportParseError)
-            }
+    /// Parse bytes of report into `SgxEnclaveReport`. Reports shorter than
+    /// `REPORT_BODY_LEN` fail with `ReportTruncated` before any field is read.
+    /// Longer reports are accepted, and the bytes following the known fields
+    /// are kept in `extra`, since future SGX revisions may extend the body and
+    /// those reports should still parse without an update. So unlike
+    /// `SgxReport::parse_from`, whose layout is fixed, this never fails with
+    /// `ReportTrailingBytes`.
+    pub fn parse_from(bytes: &[u8]) -> Result<Self, Error> {
+        let (mut report, end) = Self::parse_from_at(bytes, 0)?;
+        report.extra = bytes[end..].to_vec();
+        Ok(report)
+    }
+
+    /// Same as `parse_from`, without logging failures, for bulk validation
+    /// where malformed reports would flood the logs
+    pub fn parse_from_quiet(bytes: &[u8]) -> Result<Self, Error> {
+        let (mut report, end) = Self::parse_body_at(bytes, 0)?;
+        report.extra = bytes[end..].to_vec();
+        Ok(report)
+    }
+
+    /// Parse the report body found at `offset` in `bytes`, e.g. within a quote,
+    /// and return it along with the offset following it, so the next structure
+    /// can be parsed from there. The bytes after the body are left to the
+    /// caller, so `extra` is always empty.
+    pub fn parse_from_at(bytes: &[u8], offset: usize) -> Result<(Self, usize), Error> {
+        let parsed = Self::parse_body_at(bytes, offset);
+        if let Err(Error::ReportTruncated { expected, got }) = &parsed {
+            error!(
+                "Enclave report parsing error - bad report size: got {}, expected at least {}",
+                got, expected
+            );
+        }
+
+        parsed
+    }
+
+    fn parse_body_at<'a>(bytes: &'a [u8], offset: usize) -> Result<(Self, usize), Error> {
+        let end = offset.saturating_add(REPORT_BODY_LEN);
+        if bytes.len() < end {
+            return Err(Error::ReportTruncated {
+                expected: end,
+                got: bytes.len(),
+            });
+        }
+
+        // the length was checked above, so taking the known fields can't fail
+        let mut pos: usize = offset;
+        let mut take = |n: usize| -> &'a [u8] {
+            let ret = &bytes[pos..pos + n];
+            pos += n;
+            ret
         };
 
         // Start parsing report by bytes following specifications. Don't
         // transmute directly, since there may cause endianness issue.
         // off 48, size 16
-        let cpu_svn = <[u8; 16]>::try_from(take(16)?)?;
+        let cpu_svn = <[u8; 16]>::try_from(take(16))?;
 
         // off 64, size 4
-        let misc_select = u32::from_le_bytes(<[u8; 4]>::try_from(take(4)?)?);
+        let misc_select = u32::from_le_bytes(<[u8; 4]>::try_from(take(4))?);
 
-        // off 68, size 28
-        let _reserved = take(28)?;
+        // off 68, size 12
+        let _reserved = take(12);
+
+        // off 80, size 16
+        let isv_ext_prod_id = <[u8; 16]>::try_from(take(16))?;
 
         // off 96, size 16
-        let attributes = <[u8; 16]>::try_from(take(16)?)?;
+        let attributes = SgxReportAttributes {
+            flags: u64::from_le_bytes(<[u8; 8]>::try_from(take(8))?),
+            xfrm: u64::from_le_bytes(<[u8; 8]>::try_from(take(8))?),
+        };
 
         // off 112, size 32
-        let mr_enclave = <[u8; 32]>::try_from(take(32)?)?;
+        let mr_enclave = <[u8; 32]>::try_from(take(32))?;
 
         // off 144, size 32
-        let _reserved = take(32)?;
+        let _reserved = take(32);
 
         // off 176, size 32
-        let mr_signer = <[u8; 32]>::try_from(take(32)?)?;
+        let mr_signer = <[u8; 32]>::try_from(take(32))?;
 
-        // off 208, size 96
-        let _reserved = take(96)?;
+        // off 208, size 32
+        let _reserved = take(32);
+
+        // off 240, size 64
+        let mut config_id = [0u8; 64];
+        config_id.copy_from_slice(take(64));
 
         // off 304, size 2
-        let isv_prod_id = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        let isv_prod_id = u16::from_le_bytes(<[u8; 2]>::try_from(take(2))?);
 
         // off 306, size 2
-        let isv_svn = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        let isv_svn = u16::from_le_bytes(<[u8; 2]>::try_from(take(2))?);
+
+        // off 308, size 2
+        let config_svn = u16::from_le_bytes(<[u8; 2]>::try_from(take(2))?);
 
-        // off 308, size 60
-        let _reserved = take(60)?;
+        // off 310, size 42
+        let _reserved = take(42);
+
+        // off 352, size 16
+        let isv_family_id = <[u8; 16]>::try_from(take(16))?;
 
         // off 368, size 64
         let mut report_data = [0u8; 64];
-        let _report_data = take(64)?;
-        let mut _it = _report_data.iter();
-        for i in report_data.iter_mut() {
-            *i = *_it.next().ok_or(Error::ReportParseError)?;
-        }
-
-        if pos != bytes.len() {
-            warn!("Enclave report parsing error.");
-            return Err(Error::ReportParseError);
-        };
+        report_data.copy_from_slice(take(64));
 
-        Ok(SgxEnclaveReport {
+        let report = SgxEnclaveReport {
             cpu_svn,
             misc_select,
+            isv_ext_prod_id,
             attributes,
             mr_enclave,
             mr_signer,
             isv_prod_id,
             isv_svn,
+            config_id,
+            config_svn,
+            isv_family_id,
             report_data,
-        })
+            extra: vec![],
+        };
+
+        Ok((report, end))
     }
-}
 
-/// SGX Quote structure version
-#[derive(Debug, PartialEq)]
-#[allow(dead_code)]
-pub enum SgxQuoteVersion {
-    /// EPID quote version
-    V1(SgxEpidQuoteSigType),
-    /// EPID quote version
-    V2(SgxEpidQuoteSigType),
-    /// ECDSA quote version
-    V3(SgxEcdsaQuoteAkType),
-}
+    /// Same as `parse_from`, but also reject reports with reserved bytes set,
+    /// which Intel specifies must be zero
+    pub fn parse_from_strict(bytes: &[u8]) -> Result<Self, Error> {
+        let report = Self::parse_from(bytes)?;
 
-/// Intel EPID attestation signature type
-#[derive(Debug, PartialEq)]
-#[allow(dead_code)]
-pub enum SgxEpidQuoteSigType {
-    Unlinkable,
-    Linkable,
-}
+        for (start, len) in REPORT_BODY_RESERVED.iter() {
+            if let Some(pos) = bytes[*start..start + len].iter().position(|b| *b != 0) {
+                warn!(
+                    "Enclave report parsing error - reserved byte set at offset {}",
+                    start + pos
+                );
+                return Err(Error::NonZeroReserved {
+                    offset: start + pos,
+                });
+            }
+        }
 
-/// ECDSA attestation key type
-#[derive(Debug, PartialEq)]
-pub enum SgxEcdsaQuoteAkType {
-    /// ECDSA-256-with-P-256 curve
-    P256_256,
-    /// ECDSA-384-with-P-384 curve
-    P384_384,
-}
+        Ok(report)
+    }
 
-/// SGX Quote status
-#[derive(PartialEq, Debug)]
-pub enum SgxQuoteStatus {
-    /// EPID signature of the ISV enclave QUOTE was verified correctly and the
-    /// TCB level of the SGX platform is up-to-date.
-    OK,
-    /// EPID signature of the ISV enclave QUOTE was invalid. The content of the
-    /// QUOTE is not trustworthy.
-    ///
-    /// For DCAP, the signature over the application report is invalid.
-    SignatureInvalid,
-    /// The EPID group has been revoked. When this value is returned, the
-    /// revocation Reason field of the Attestation Verification Report will
-    /// contain revocation reason code for this EPID group as reported in the
-    /// EPID Group CRL. The content of the QUOTE is not trustworthy.
-    GroupRevoked,
-    /// The EPID private key used to sign the QUOTE has been revoked by
-    /// signature. The content of the QUOTE is not trustworthy.
-    SignatureRevoked,
-    /// The EPID private key used to sign the QUOTE has been directly revoked
-    /// (not by signature). The content of the QUOTE is not trustworthy.
-    ///
-    /// For DCAP, the attestation key or platform has been revoked.
-    KeyRevoked,
-    /// SigRL version in ISV enclave QUOTE does not match the most recent
-    /// version of the SigRL. In rare situations, after SP retrieved the SigRL
-    /// from IAS and provided it to the platform, a newer version of the SigRL
-    /// is madeavailable. As a result, the Attestation Verification Report will
-    /// indicate SIGRL_VERSION_MISMATCH. SP can retrieve the most recent version
-    /// of SigRL from the IAS and request the platform to perform remote
-    /// attestation again with the most recent version of SigRL. If the platform
-    /// keeps failing to provide a valid QUOTE matching with the most recent
-    /// version of the SigRL, the content of the QUOTE is not trustworthy.
-    SigrlVersionMismatch,
-    /// The EPID signature of the ISV enclave QUOTE has been verified correctly,
-    /// but the TCB level of SGX platform is outdated (for further details see
-    /// Advisory IDs). The platform has not been identified as compromised and
-    /// thus it is not revoked. It is up to the Service Provider to decide
-    /// whether or not to trust the content of the QUOTE, andwhether or not to
-    /// trust the platform performing the attestation to protect specific
-    /// sensitive information.
-    GroupOutOfDate,
-    /// The EPID signature of the ISV enclave QUOTE has been verified correctly,
-    /// but additional configuration of SGX platform may be needed(for further
-    /// details see Advisory IDs). The platform has not been identified as
-    /// compromised and thus it is not revoked. It is up to the Service Provider
-    /// to decide whether or not to trust the content of the QUOTE, and whether
-    /// or not to trust the platform performing the attestation to protect
-    /// specific sensitive information.
-    ///
-    /// For DCAP, The Quote verification passed and the platform is patched to
-    /// the latest TCB level but additional configuration of the SGX
-    /// platform may be needed.
-    ConfigurationNeeded,
-    /// The EPID signature of the ISV enclave QUOTE has been verified correctly
-    /// but due to certain issues affecting the platform, additional SW
-    /// Hardening in the attesting SGX enclaves may be needed.The relying party
-    /// should evaluate the potential risk of an attack leveraging the relevant
-    /// issues on the attesting enclave, and whether the attesting enclave
-    /// employs adequate software hardening to mitigate the risk.
-    SwHardeningNeeded,
-    /// The EPID signature of the ISV enclave QUOTE has been verified correctly
-    /// but additional configuration for the platform and SW Hardening in the
-    /// attesting SGX enclaves may be needed. The platform has not been
-    /// identified as compromised and thus it is not revoked. It is up to the
-    /// Service Provider to decide whether or not to trust the content of the
-    /// QUOTE. The relying party should also evaluate the potential risk of an
-    /// attack leveraging the relevant issues on the attestation enclave, and
-    /// whether the attesting enclave employs adequate software hardening to
-    /// mitigate the risk.
-    ConfigurationAndSwHardeningNeeded,
-    /// DCAP specific quote status. The Quote is good but TCB level of the
-    /// platform is out of date. The platform needs patching to be at the latest
-    /// TCB level.
-    OutOfDate,
-    /// DCAP specific quote status. The Quote is good but the TCB level of the
-    /// platform is out of date and additional configuration of the SGX Platform
-    /// at its current patching level may be needed. The platform needs patching
-    /// to be at the latest TCB level.
-    OutOfDateConfigurationNeeded,
-    /// Other unknown bad status.
-    UnknownBadStatus,
-}
+    /// Short identifier of the report for logs: the first 8 bytes of the
+    /// SHA256 of `to_bytes`, hex encoded
+    pub fn fingerprint(&self) -> String {
+        hex::encode(&sha_256(&self.to_bytes())[..8])
+    }
 
-impl From<&SgxQuoteStatus> for NodeAuthResult {
-    fn from(status: &SgxQuoteStatus) -> Self {
-        match status {
-            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded => {
-                NodeAuthResult::SwHardeningAndConfigurationNeeded
-            }
-            SgxQuoteStatus::ConfigurationNeeded => NodeAuthResult::ConfigurationNeeded,
-            SgxQuoteStatus::GroupOutOfDate => NodeAuthResult::GroupOutOfDate,
-            SgxQuoteStatus::KeyRevoked => NodeAuthResult::KeyRevoked,
-            SgxQuoteStatus::SigrlVersionMismatch => NodeAuthResult::SigrlVersionMismatch,
-            SgxQuoteStatus::SignatureRevoked => NodeAuthResult::SignatureRevoked,
-            SgxQuoteStatus::GroupRevoked => NodeAuthResult::GroupRevoked,
-            _ => NodeAuthResult::BadQuoteStatus,
+    /// Serialize to the layout read by `parse_from`, with the reserved regions
+    /// zeroed and `extra` appended
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(REPORT_BODY_LEN + self.extra.len());
+
+        // off 48, size 16
+        bytes.extend_from_slice(&self.cpu_svn);
+        // off 64, size 4
+        bytes.extend_from_slice(&self.misc_select.to_le_bytes());
+        // off 68, size 12
+        bytes.extend_from_slice(&[0u8; 12]);
+        // off 80, size 16
+        bytes.extend_from_slice(&self.isv_ext_prod_id);
+        // off 96, size 16
+        bytes.extend_from_slice(&self.attributes.flags.to_le_bytes());
+        bytes.extend_from_slice(&self.attributes.xfrm.to_le_bytes());
+        // off 112, size 32
+        bytes.extend_from_slice(&self.mr_enclave);
+        // off 144, size 32
+        bytes.extend_from_slice(&[0u8; 32]);
+        // off 176, size 32
+        bytes.extend_from_slice(&self.mr_signer);
+        // off 208, size 32
+        bytes.extend_from_slice(&[0u8; 32]);
+        // off 240, size 64
+        bytes.extend_from_slice(&self.config_id);
+        // off 304, size 2
+        bytes.extend_from_slice(&self.isv_prod_id.to_le_bytes());
+        // off 306, size 2
+        bytes.extend_from_slice(&self.isv_svn.to_le_bytes());
+        // off 308, size 2
+        bytes.extend_from_slice(&self.config_svn.to_le_bytes());
+        // off 310, size 42
+        bytes.extend_from_slice(&[0u8; 42]);
+        // off 352, size 16
+        bytes.extend_from_slice(&self.isv_family_id);
+        // off 368, size 64
+        bytes.extend_from_slice(&self.report_data);
+        // off 432
+        bytes.extend_from_slice(&self.extra);
+
+        bytes
+    }
+
+    /// The native SDK struct of the report, e.g. for `rsgx_verify_report`.
+    /// The reserved regions are zeroed, and `extra` is left out since the
+    /// struct has no room for it.
+    pub fn to_report_body(&self) -> sgx_report_body_t {
+        let mut body = sgx_report_body_t::default();
+        body.cpu_svn.svn = self.cpu_svn;
+        body.misc_select = self.misc_select;
+        body.isv_ext_prod_id = self.isv_ext_prod_id;
+        body.attributes.flags = self.attributes.flags;
+        body.attributes.xfrm = self.attributes.xfrm;
+        body.mr_enclave.m = self.mr_enclave;
+        body.mr_signer.m = self.mr_signer;
+        body.config_id = self.config_id;
+        body.isv_prod_id = self.isv_prod_id;
+        body.isv_svn = self.isv_svn;
+        body.config_svn = self.config_svn;
+        body.isv_family_id = self.isv_family_id;
+        body.report_data.d = self.report_data;
+        body
+    }
+
+    /// Public key of the node that generated the report, which is bound to the
+    /// first 32 bytes of `report_data`. An all-zero key means no key was bound.
+    pub fn get_owner_key(&self) -> Result<NodeAuthPublicKey, Error> {
+        let key = self.get_owner_key_unchecked();
+        if key == [0u8; PUBLIC_KEY_SIZE] {
+            warn!("Enclave report does not contain an owner key");
+            return Err(Error::EmptyOwnerKey);
         }
+
+        Ok(key)
     }
-}
 
-impl From<&str> for SgxQuoteStatus {
-    /// Convert from str status from the report to enum.
-    fn from(status: &str) -> Self {
-        match status {
-            "OK" => SgxQuoteStatus::OK,
-            "SIGNATURE_INVALID" => SgxQuoteStatus::SignatureInvalid,
-            "GROUP_REVOKED" => SgxQuoteStatus::GroupRevoked,
-            "SIGNATURE_REVOKED" => SgxQuoteStatus::SignatureRevoked,
-            "KEY_REVOKED" => SgxQuoteStatus::KeyRevoked,
-            "SIGRL_VERSION_MISMATCH" => SgxQuoteStatus::SigrlVersionMismatch,
-            "GROUP_OUT_OF_DATE" => SgxQuoteStatus::GroupOutOfDate,
-            "OUT_OF_DATE" => SgxQuoteStatus::OutOfDate,
-            "OUT_OF_DATE_CONFIGURATION_NEEDED" => SgxQuoteStatus::OutOfDateConfigurationNeeded,
-            "CONFIGURATION_NEEDED" => SgxQuoteStatus::ConfigurationNeeded,
-            "SW_HARDENING_NEEDED" => SgxQuoteStatus::SwHardeningNeeded,
-            "CONFIGURATION_AND_SW_HARDENING_NEEDED" => {
-                SgxQuoteStatus::ConfigurationAndSwHardeningNeeded
+    /// Same as `get_owner_key`, without rejecting an all-zero key
+    pub fn get_owner_key_unchecked(&self) -> NodeAuthPublicKey {
+        let mut key = [0u8; PUBLIC_KEY_SIZE];
+        key.copy_from_slice(&self.report_data[0..PUBLIC_KEY_SIZE]);
+        key
+    }
+
+    /// `report_data` binding `data`, zero-padded to 64 bytes. Fails rather than
+    /// truncating data that doesn't fit.
+    pub fn with_report_data(data: &[u8]) -> Result<[u8; 64], Error> {
+        let mut report_data = [0u8; 64];
+        match report_data.get_mut(..data.len()) {
+            Some(prefix) => prefix.copy_from_slice(data),
+            None => {
+                warn!("{} bytes of data don't fit in the report data", data.len());
+                return Err(Error::ReportDataTooLong(data.len()));
             }
-            _ => SgxQuoteStatus::UnknownBadStatus,
         }
+
+        Ok(report_data)
     }
-}
 
-/// An application that hosts an enclave can ask the enclave to produce a report
-/// (`SgxEnclaveReport`) and then pass this report to a platform service
-/// (Quoting Enclave) to produce a type of credential that reflects the enclave
-/// and platform state. The quote can be passed to entities off the platform,
-/// and verified using Intel EPID signature verification techniques.
-pub struct SgxQuote {
-    /// Version of the quote structure
-    pub version: SgxQuoteVersion,
-    /// ID of the Intel EPID group of the platform belongs to
-    pub gid: u32,
-    /// Security version number of Quoting Enclave
-    pub isv_svn_qe: u16,
-    /// Security version number of PCE
-    pub isv_svn_pce: u16,
-    /// Vendor ID of Quoting Enclave
-    pub qe_vendor_id: Uuid,
-    /// User data
-    pub user_data: [u8; 20],
-    /// Report generated by the enclave
-    pub isv_enclave_report: SgxEnclaveReport,
-}
+    /// Bind `key` to the report as its owner key, leaving the second half of
+    /// `report_data` as it is
+    pub fn set_owner_key(&mut self, key: &NodeAuthPublicKey) {
+        self.report_data[..PUBLIC_KEY_SIZE].copy_from_slice(key);
+    }
 
-impl std::fmt::Debug for SgxQuote {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "version: {:?}", self.version)?;
-        writeln!(f, "gid: {}", self.gid)?;
-        writeln!(f, "isv_svn_qe: {}", self.isv_svn_qe)?;
-        writeln!(f, "isv_svn_pce: {}", self.isv_svn_pce)?;
-        writeln!(f, "qe_vendor_id: {}", self.qe_vendor_id)?;
-        writeln!(f, "user_data: {:?}", &self.user_data)?;
-        writeln!(f, "isv_enclave_report: \n{:?}", self.isv_enclave_report)
+    /// Both keys bound to `report_data` at registration: the node auth key in
+    /// the first 32 bytes and the encryption key in the last 32. Neither may be
+    /// all zeros.
+    pub fn get_keys(&self) -> Result<(NodeAuthPublicKey, NodeAuthPublicKey), Error> {
+        let owner_key = self.get_owner_key()?;
+
+        let encryption_key = self.get_report_data_tail();
+        if encryption_key == [0u8; PUBLIC_KEY_SIZE] {
+            warn!("Enclave report does not contain an encryption key");
+            return Err(Error::EmptyEncryptionKey);
+        }
+
+        Ok((owner_key, encryption_key))
     }
-}
 
-impl SgxQuote {
-    /// Parse from bytes to `SgxQuote`.
-    // just unused in SW mode
-    #[allow(dead_code)]
-    fn parse_from<'a>(bytes: &'a [u8]) -> Result<Self, Error> {
-        let mut pos: usize = 0;
-        let mut take = |n: usize| -> Result<&'a [u8], Error> {
-            if n > 0 && bytes.len() >= pos + n {
-                let ret = &bytes[pos..pos + n];
-                pos += n;
-                Ok(ret)
-            } else {
-                warn!("Quote parsing error.");
-                Err(Error::ReportParseError)
-            }
-        };
+    /// Second half of `report_data`, which holds the hash of the TLS
+    /// certificate or of the challenge the report answers
+    pub fn get_report_data_tail(&self) -> [u8; 32] {
+        let mut tail = [0u8; 32];
+        tail.copy_from_slice(&self.report_data[32..64]);
+        tail
+    }
 
-        // Parse by bytes according to specifications.
-        // off 0, size 2 + 2
-        let version = match u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?) {
-            1 => {
-                let signature_type = match u16::from_le_bytes(<[u8; 2]>::try_from(
-                    take(2).map_err(|_| Error::ReportParseError)?,
-                )?) {
-                    0 => SgxEpidQuoteSigType::Unlinkable,
-                    1 => SgxEpidQuoteSigType::Linkable,
-                    _ => {
-                        warn!("Invalid v1 quote signature type");
-                        return Err(Error::ReportParseError);
-                    }
-                };
-                SgxQuoteVersion::V1(signature_type)
-            }
-            2 => {
-                let signature_type = match u16::from_le_bytes(<[u8; 2]>::try_from(
-                    take(2).map_err(|_| Error::ReportParseError)?,
-                )?) {
-                    0 => SgxEpidQuoteSigType::Unlinkable,
-                    1 => SgxEpidQuoteSigType::Linkable,
-                    _ => {
-                        warn!("Invalid v2 quote signature type");
-                        return Err(Error::ReportParseError);
-                    }
-                };
-                SgxQuoteVersion::V2(signature_type)
-            }
-            3 => {
-                let attestation_key_type = match u16::from_le_bytes(<[u8; 2]>::try_from(
-                    take(2).map_err(|_| Error::ReportParseError)?,
-                )?) {
-                    2 => SgxEcdsaQuoteAkType::P256_256,
-                    3 => SgxEcdsaQuoteAkType::P384_384,
-                    _ => {
-                        warn!("Quote parsing error - ecdsa quote type invalid");
-                        return Err(Error::ReportParseError);
-                    }
-                };
-                SgxQuoteVersion::V3(attestation_key_type)
-            }
-            _ => {
-                warn!("Quote parsing error - Unknown quote version");
-                return Err(Error::ReportParseError);
-            }
-        };
+    /// Bytes of `report_data` in `range`, or `None` if it's out of bounds
+    pub fn report_data_field(&self, range: std::ops::Range<usize>) -> Option<&[u8]> {
+        self.report_data.get(range)
+    }
 
-        // off 4, size 4
-        let gid = u32::from_le_bytes(<[u8; 4]>::try_from(take(4).map_err(|_| {
-            warn!("Failed to parse quote gid");
+    /// Bytes of `report_data` in `range` as a string, without the trailing null
+    /// bytes it's padded with. Fails if `range` is out of bounds or the bytes
+    /// aren't UTF-8.
+    pub fn report_data_str(&self, range: std::ops::Range<usize>) -> Result<&str, Error> {
+        let field = self.report_data_field(range.clone()).ok_or_else(|| {
+            warn!("Report data range {:?} is out of bounds", range);
             Error::ReportParseError
-        })?)?);
+        })?;
+        let len = field.iter().rposition(|b| *b != 0).map_or(0, |pos| pos + 1);
 
-        // off 8, size 2
-        let isv_svn_qe = u16::from_le_bytes(<[u8; 2]>::try_from(take(2).map_err(|_| {
-            warn!("Failed to parse quote isv svn qe");
+        std::str::from_utf8(&field[..len]).map_err(|_| {
+            warn!("Report data in {:?} is not a UTF-8 string", range);
             Error::ReportParseError
-        })?)?);
+        })
+    }
 
-        // off 10, size 2
-        let isv_svn_pce = u16::from_le_bytes(<[u8; 2]>::try_from(take(2).map_err(|_| {
-            warn!("Failed to parse quote isv svn");
-            Error::ReportParseError
-        })?)?);
+    /// Whether `report_data` is all zeros, as in a report that binds no data
+    pub fn report_data_is_empty(&self) -> bool {
+        ct_eq(&self.report_data, &[0u8; 64])
+    }
 
-        // off 12, size 16
-        let qe_vendor_id_raw =
-            <[u8; 16]>::try_from(take(16).map_err(|_| Error::ReportParseError)?)?;
-        let qe_vendor_id = Uuid::from_slice(&qe_vendor_id_raw).map_err(|_| {
-            warn!("Failed to parse quote vendor id");
-            Error::ReportParseError
-        })?;
+    /// Verify that `report_data` holds `expected` at `offset`, e.g. the nonce of
+    /// a handshake
+    pub fn verify_report_data_contains(
+        &self,
+        expected: &[u8],
+        offset: usize,
+    ) -> Result<(), NodeAuthResult> {
+        let field = offset
+            .checked_add(expected.len())
+            .and_then(|end| self.report_data_field(offset..end))
+            .ok_or_else(|| {
+                warn!(
+                    "{} bytes at offset {} don't fit in the report data",
+                    expected.len(),
+                    offset
+                );
+                NodeAuthResult::ReportDataOutOfRange
+            })?;
 
-        // off 28, size 20
-        let user_data = <[u8; 20]>::try_from(take(20).map_err(|_| {
-            warn!("Failed to parse quote user data");
-            Error::ReportParseError
-        })?)?;
+        if !ct_eq(field, expected) {
+            warn!("Report data does not contain the expected value");
+            return Err(NodeAuthResult::ReportDataMismatch);
+        }
 
-        // off 48, size 384
-        let isv_enclave_report = SgxEnclaveReport::parse_from(take(384).map_err(|_| {
-            warn!("Failed to parse enclave report");
-            Error::ReportParseError
-        })?)?;
+        Ok(())
+    }
 
-        if pos != bytes.len() {
-            warn!("Quote parsing error - Quote size different from expected");
-            return Err(Error::ReportParseError);
+    /// Verify the binding of an interactive remote attestation: the first half
+    /// of `report_data` must hold the expected key, and the second half the
+    /// SHA256 of the challenge issued by the verifier
+    pub fn verify_transcript(
+        &self,
+        expected_key: &NodeAuthPublicKey,
+        challenge: &[u8; 32],
+    ) -> Result<(), NodeAuthResult> {
+        if !ct_eq(&self.get_owner_key_unchecked(), expected_key) {
+            warn!("Report data does not contain the expected public key");
+            return Err(NodeAuthResult::ReportDataMismatch);
+        }
+
+        if !ct_eq(&self.get_report_data_tail(), &sha_256(challenge)) {
+            warn!("Report data does not contain the expected challenge");
+            return Err(NodeAuthResult::ReportDataMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Verify that the platform is patched at least as required by `matrix` for
+    /// the security version of the enclave
+    pub fn verify_svn_consistency(&self, matrix: &SvnMatrix) -> Result<(), NodeAuthResult> {
+        if let Some(min_cpu_svn) = matrix.min_cpu_svn(self.isv_svn) {
+            let missing = CpuSvn::from(self.cpu_svn).missing_updates_vs(min_cpu_svn);
+            if !missing.is_empty() {
+                warn!(
+                    "cpu_svn {:?} is missing updates {:?} required for isv_svn {}",
+                    self.cpu_svn, missing, self.isv_svn
+                );
+                return Err(NodeAuthResult::SvnInconsistent);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Verify that the report was generated by a 64-bit enclave
+    pub fn verify_64bit(&self) -> Result<(), NodeAuthResult> {
+        self.fields().verify_64bit()
+    }
+
+    /// Whether the report identifies this very enclave rather than another
+    /// instance of it: every field but `report_data` must match our own report,
+    /// including the platform's `cpu_svn`. `verify` only checks the fields
+    /// shared by all the instances of this enclave.
+    pub fn is_self_report(&self) -> bool {
+        let body = match get_cached_report() {
+            Ok(self_report) => self_report.body,
+            Err(_) => return false,
+        };
+        let attributes = SgxReportAttributes {
+            flags: body.attributes.flags,
+            xfrm: body.attributes.xfrm,
         };
 
-        Ok(Self {
-            version,
-            gid,
-            isv_svn_qe,
-            isv_svn_pce,
-            qe_vendor_id,
-            user_data,
-            isv_enclave_report,
-        })
+        ct_eq(&self.mr_enclave, &body.mr_enclave.m)
+            & ct_eq(&self.mr_signer, &body.mr_signer.m)
+            & ct_eq(&self.cpu_svn, &body.cpu_svn.svn)
+            & self.attributes.ct_eq(&attributes, !0)
+            & (self.misc_select == body.misc_select)
+            & (self.isv_ext_prod_id == body.isv_ext_prod_id)
+            & (self.isv_prod_id == body.isv_prod_id)
+            & (self.isv_svn == body.isv_svn)
+            & (self.config_id == body.config_id)
+            & (self.config_svn == body.config_svn)
+            & (self.isv_family_id == body.isv_family_id)
     }
-}
 
-#[cfg(all(feature = "SGX_MODE_HW", not(feature = "production")))]
-const WHITELISTED_ADVISORIES: &[&str] = &[
-    "INTEL-SA-00334",
-    "INTEL-SA-00219",
-    "INTEL-SA-00615",
-    "INTEL-SA-00657",
-    "INTEL-SA-00767",
-];
+    /// The fields that differ between this report and `other`, in the order
+    /// of the report body, e.g. to tell why a report didn't verify
+    pub fn diff(&self, other: &SgxEnclaveReport) -> Vec<ReportFieldDiff> {
+        let fields = [
+            (self.cpu_svn != other.cpu_svn, ReportFieldDiff::CpuSvn),
+            (
+                self.misc_select != other.misc_select,
+                ReportFieldDiff::MiscSelect,
+            ),
+            (
+                self.isv_ext_prod_id != other.isv_ext_prod_id,
+                ReportFieldDiff::IsvExtProdId,
+            ),
+            (
+                self.attributes != other.attributes,
+                ReportFieldDiff::Attributes,
+            ),
+            (
+                self.mr_enclave != other.mr_enclave,
+                ReportFieldDiff::MrEnclave,
+            ),
+            (self.mr_signer != other.mr_signer, ReportFieldDiff::MrSigner),
+            (
+                self.isv_prod_id != other.isv_prod_id,
+                ReportFieldDiff::IsvProdId,
+            ),
+            (self.isv_svn != other.isv_svn, ReportFieldDiff::IsvSvn),
+            (self.config_id != other.config_id, ReportFieldDiff::ConfigId),
+            (
+                self.config_svn != other.config_svn,
+                ReportFieldDiff::ConfigSvn,
+            ),
+            (
+                self.isv_family_id != other.isv_family_id,
+                ReportFieldDiff::IsvFamilyId,
+            ),
+            (
+                self.report_data != other.report_data,
+                ReportFieldDiff::ReportData,
+            ),
+            (self.extra != other.extra, ReportFieldDiff::Extra),
+        ];
 
-#[cfg(all(feature = "SGX_MODE_HW", feature = "production"))]
-const WHITELISTED_ADVISORIES: &[&str] = &[
-    "INTEL-SA-00334",
-    "INTEL-SA-00219",
-    "INTEL-SA-00615",
-    "INTEL-SA-00657",
-    "INTEL-SA-00767",
-];
+        fields
+            .iter()
+            .filter(|(differs, _)| *differs)
+            .map(|(_, field)| *field)
+            .collect()
+    }
 
-lazy_static! {
-    static ref ADVISORY_DESC: HashMap<&'static str, &'static str> = [
-        (
-            "INTEL-SA-00161",
-            "You must disable hyperthreading in the BIOS"
-        ),
-        (
-            "INTEL-SA-00289",
-            "You must disable overclocking/undervolting in the BIOS"
-        ),
-    ]
-    .iter()
-    .copied()
-    .collect();
-}
+    /// Whether `other` was generated on the same platform as this report, i.e.
+    /// with the same `cpu_svn` and attributes. Compared in constant time.
+    pub fn same_platform_as(&self, other: &SgxEnclaveReport) -> bool {
+        ct_eq(&self.cpu_svn, &other.cpu_svn) & self.attributes.ct_eq(&other.attributes, !0)
+    }
 
-#[derive(Debug)]
-pub struct AdvisoryIDs(pub Vec<String>);
+    /// Verify that the attributes match those of this enclave, comparing only
+    /// the flags in `flags_mask` and the XSAVE features in `xfrm_mask`, e.g.
+    /// `SECURITY_FLAGS_MASK` to tolerate features that vary across CPUs
+    pub fn verify_attributes_masked(
+        &self,
+        flags_mask: u64,
+        xfrm_mask: u64,
+    ) -> Result<(), NodeAuthResult> {
+        let self_report = get_cached_report()?;
+        let expected = SgxReportAttributes {
+            flags: self_report.body.attributes.flags,
+            xfrm: self_report.body.attributes.xfrm,
+        };
 
-#[cfg(feature = "SGX_MODE_HW")]
-impl AdvisoryIDs {
-    pub(crate) fn vulnerable(&self) -> Vec<String> {
-        let mut vulnerable: Vec<String> = vec![];
-        for i in self.0.iter() {
-            if !WHITELISTED_ADVISORIES.contains(&i.as_str()) {
-                vulnerable.push(i.clone());
-                if let Some(v) = ADVISORY_DESC.get(&i.as_str()) {
-                    vulnerable.push((*v).to_string())
-                }
-            }
+        if !self
+            .attributes
+            .ct_eq_masked(&expected, flags_mask, xfrm_mask)
+        {
+            warn!(
+                "Got a report with different attributes than expected: {:?}",
+                self.attributes
+            );
+            return Err(NodeAuthResult::AttributesMismatch);
         }
-        vulnerable
+
+        Ok(())
     }
-}
 
-/// A report that can be signed by Intel EPID (which generates
-/// `EndorsedAttestationReport`) and then sent off of the platform to be
-/// verified by remote client.
-#[derive(Debug)]
-pub struct AttestationReport {
-    /// The freshness of the report, i.e., elapsed time after acquiring the
-    /// report in seconds.
-    pub timestamp: u64,
-    /// Quote status
-    pub sgx_quote_status: SgxQuoteStatus,
-    /// Content of the quote
-    pub sgx_quote_body: SgxQuote,
-    pub platform_info_blob: Option<Vec<u8>>,
-    pub advisory_ids: AdvisoryIDs,
-    pub tcb_eval_data_number: u16,
-}
+    /// Verify that the report wasn't generated by an enclave running in debug
+    /// mode, whose memory can be inspected
+    pub fn verify_not_debug(&self) -> Result<(), NodeAuthResult> {
+        self.fields().verify_not_debug()
+    }
 
-impl AttestationReport {
-    /// Construct a AttestationReport from a X509 certificate and verify
-    /// attestation report with the report_ca_cert which is from the attestation
-    /// service provider.
-    // just unused in SW mode
-    #[allow(dead_code)]
-    pub fn from_cert(cert: &[u8]) -> Result<Self, Error> {
-        let payload = get_netscape_comment(cert).map_err(|_err| {
-            error!("Failed to get netscape comment");
-            Error::ReportParseError
-        })?;
+    /// Verify that the enclave was signed by one of `allowed_signers`. This
+    /// complements the mr_enclave check, which says what code is running but
+    /// not who vouched for it. An empty allowlist rejects every report.
+    pub fn verify_signer(&self, allowed_signers: &[[u8; 32]]) -> Result<(), NodeAuthResult> {
+        self.fields().verify_signer(allowed_signers)
+    }
 
-        // Convert to endorsed report
-        let report: EndorsedAttestationReport = serde_json::from_slice(&payload)?;
+    /// Verify that the report was generated by the enclave product `expected`,
+    /// so reports of other products by the same signer aren't accepted
+    pub fn verify_prod_id(&self, expected: u16) -> Result<(), NodeAuthResult> {
+        self.fields().verify_prod_id(expected)
+    }
 
-        // Verify report's signature - aka intel's signing cert
-        let signing_cert = webpki::EndEntityCert::from(&report.signing_cert).map_err(|_err| {
-            error!("Failed to validate signature");
-            Error::ReportParseError
-        })?;
+    /// Verify that the enclave was loaded with the MISC features `expected`,
+    /// e.g. none for an enclave that doesn't use any
+    pub fn verify_misc_select(&self, expected: u32) -> Result<(), NodeAuthResult> {
+        self.fields().verify_misc_select(expected)
+    }
 
-        let (ias_cert, root_store) = get_ias_auth_config();
+    /// Verify that the report was generated by the same enclave product as the
+    /// one running this code, without pinning the product id
+    pub fn verify_same_product(&self) -> Result<(), NodeAuthResult> {
+        let self_report = get_cached_report()?;
+        self.verify_prod_id(self_report.body.isv_prod_id)
+    }
 
-        let trust_anchors: Vec<webpki::TrustAnchor> = root_store
-            .roots
-            .iter()
-            .map(|cert| cert.to_trust_anchor())
-            .collect();
+    /// Verify that the report was generated by the same enclave as the one
+    /// running this code, i.e. that the measurement and attributes match our
+    /// own report.
+    pub fn verify(&self) -> Result<(), NodeAuthResult> {
+        self.verify_with_min_svn(0)
+    }
 
-        let chain: Vec<&[u8]> = vec![&ias_cert];
+    /// Same as `verify`, and also reject enclaves whose `isv_svn` is below
+    /// `min_isv_svn`, even when the measurement matches
+    pub fn verify_with_min_svn(&self, min_isv_svn: u16) -> Result<(), NodeAuthResult> {
+        self.verify_with_policy(&VerifyPolicy {
+            min_isv_svn,
+            ..Default::default()
+        })
+    }
 
-        // set as 04.11.23(dd.mm.yy) - should be valid for the foreseeable future, and not rely on SystemTime
-        let time_stamp = webpki::Time::from_seconds_since_unix_epoch(1723218496);
+    /// Same as `verify`, but accept any `mr_enclave` in `allowed` instead of only
+    /// the measurement of this enclave, e.g. the old and new measurements
+    /// during an upgrade. The attributes must still match this enclave.
+    pub fn verify_against_measurements(&self, allowed: &[[u8; 32]]) -> Result<(), NodeAuthResult> {
+        self.verify_with_policy(&VerifyPolicy {
+            mr_enclave: Some(Box::new(AnyOf(allowed.to_vec()))),
+            ..Default::default()
+        })
+    }
 
-        // note: there's no way to not validate the time, and we don't want to write this code
-        // ourselves. We also can't just ignore the error message, since that means that the rest of
-        // the validation didn't happen (time is validated early on)
-        match signing_cert.verify_is_valid_tls_server_cert(
-            SUPPORTED_SIG_ALGS,
-            &webpki::TLSServerTrustAnchors(&trust_anchors),
-            &chain,
-            time_stamp,
-        ) {
-            Ok(_) => info!("Certificate verified successfully"),
+    /// Same as `verify`, and also reject platforms whose `cpu_svn` is below
+    /// `min` in any of its components, e.g. after a microcode rollback
+    pub fn verify_with_min_cpu_svn(&self, min: [u8; 16]) -> Result<(), NodeAuthResult> {
+        self.verify_with_policy(&VerifyPolicy {
+            min_cpu_svn: Some(min),
+            ..Default::default()
+        })
+    }
+
+    /// Same as `verify`, with the additional checks required by `policy`
+    pub fn verify_with_policy(&self, policy: &VerifyPolicy) -> Result<(), NodeAuthResult> {
+        self.verify_posture(policy, true)
+    }
+
+    /// Verify with `policy`, and on failure also tell whether the report would
+    /// have been accepted without the production posture checks (debug mode,
+    /// 64-bit mode), i.e. whether it's a real problem or a matter of strictness
+    pub fn verify_detailed(&self, policy: &VerifyPolicy) -> VerifyDetails {
+        let result = self.verify_posture(policy, true);
+        let would_pass_permissive = result.is_ok() || self.verify_posture(policy, false).is_ok();
+
+        VerifyDetails {
+            result,
+            would_pass_permissive,
+        }
+    }
+
+    /// Verify with `policy` and collect every check that failed, rather than
+    /// stopping at the first one, for troubleshooting
+    pub fn verify_report(&self, policy: &VerifyPolicy, advisories: &AdvisoryIDs) -> VerifyReport {
+        let self_report = match get_cached_report() {
+            Ok(self_report) => self_report,
             Err(e) => {
-                error!("Certificate verification error {:?}", e);
-                return Err(Error::ReportValidationError);
+                return VerifyReport {
+                    failed_checks: vec![FailedCheck {
+                        check: "self_report",
+                        got: e.to_string(),
+                        expected: "report of this enclave".to_string(),
+                    }],
+                    result: Err(e),
+                    advisory_ids: advisories.0.clone(),
+                    expected_mr_enclave: [0; 32],
+                    expected_mr_signer: [0; 32],
+                };
             }
         };
+        let mut failed_checks = vec![];
 
-        // Verify the signature against the signing cert
-        match signing_cert.verify_signature(
-            &webpki::RSA_PKCS1_2048_8192_SHA256,
-            &report.report,
-            &report.signature,
-        ) {
-            Ok(_) => info!("Signature verified successfully"),
-            Err(e) => {
-                warn!("Signature verification error {:?}", e);
-                return Err(Error::ReportParseError);
+        let expected_mr_enclave = match &policy.mr_enclave {
+            Some(matcher) if !matcher.matches(&self.mr_enclave) => Some(format!("{:?}", matcher)),
+            None if !ct_eq(&self.mr_enclave, &self_report.body.mr_enclave.m) => {
+                Some(hex::encode(self_report.body.mr_enclave.m))
             }
+            _ => None,
+        };
+        if let Some(expected) = expected_mr_enclave {
+            failed_checks.push(FailedCheck {
+                check: "mr_enclave",
+                got: hex::encode(self.mr_enclave),
+                expected,
+            });
         }
 
-        // Verify and extract information from attestation report
-        let attn_report: Value = serde_json::from_slice(&report.report)?;
-        trace!("attn_report: {}", attn_report);
+        if !policy.allowed_signers.is_empty()
+            && self.verify_signer(&policy.allowed_signers).is_err()
+        {
+            failed_checks.push(FailedCheck {
+                check: "mr_signer",
+                got: hex::encode(self.mr_signer),
+                expected: policy
+                    .allowed_signers
+                    .iter()
+                    .map(hex::encode)
+                    .collect::<Vec<_>>()
+                    .join(" | "),
+            });
+        }
 
-        // Verify API version is supported
-        let version = attn_report["version"]
-            .as_u64()
-            .ok_or(Error::ReportParseError)?;
+        let expected_attributes = policy.attributes.unwrap_or(SgxReportAttributes {
+            flags: self_report.body.attributes.flags,
+            xfrm: self_report.body.attributes.xfrm,
+        });
+        if !self.attributes.ct_eq(&expected_attributes, !0) {
+            failed_checks.push(FailedCheck {
+                check: "attributes",
+                got: format!("{:?}", self.attributes),
+                expected: format!("{:?}", expected_attributes),
+            });
+        }
 
-        if version != 5 {
-            warn!("API version incompatible");
-            return Err(Error::ReportParseError);
-        };
+        match policy.isv_prod_id {
+            Some(expected) if self.isv_prod_id != expected => {
+                failed_checks.push(FailedCheck {
+                    check: "isv_prod_id",
+                    got: self.isv_prod_id.to_string(),
+                    expected: expected.to_string(),
+                });
+            }
+            _ => {}
+        }
 
-        let mut platform_info_blob = None;
-        if let Some(blob) = attn_report["platformInfoBlob"].as_str() {
-            let as_binary = hex::decode(blob).map_err(|_| {
-                warn!("Error parsing platform info");
-                Error::ReportParseError
-            })?;
-            platform_info_blob = Some(as_binary)
+        match policy.misc_select {
+            Some(expected) if self.misc_select != expected => {
+                failed_checks.push(FailedCheck {
+                    check: "misc_select",
+                    got: format!("{:#x}", self.misc_select),
+                    expected: format!("{:#x}", expected),
+                });
+            }
+            _ => {}
         }
 
-        // Get quote status
-        let sgx_quote_status = {
-            let status_string = attn_report["isvEnclaveQuoteStatus"]
-                .as_str()
-                .ok_or_else(|| {
-                    warn!("Error parsing enclave quote status");
+        if self.isv_svn < policy.min_isv_svn {
+            failed_checks.push(FailedCheck {
+                check: "isv_svn",
+                got: self.isv_svn.to_string(),
+                expected: format!(">= {}", policy.min_isv_svn),
+            });
+        }
+
+        if let Some(min) = policy.min_cpu_svn {
+            if !CpuSvn::from(self.cpu_svn)
+                .missing_updates_vs(&CpuSvn(min))
+                .is_empty()
+            {
+                failed_checks.push(FailedCheck {
+                    check: "cpu_svn",
+                    got: hex::encode(self.cpu_svn),
+                    expected: format!(">= {}", hex::encode(min)),
+                });
+            }
+        }
+
+        VerifyReport {
+            result: self.verify_with_policy(policy),
+            failed_checks,
+            advisory_ids: advisories.0.clone(),
+            expected_mr_enclave: self_report.body.mr_enclave.m,
+            expected_mr_signer: self_report.body.mr_signer.m,
+        }
+    }
+
+    fn verify_posture(&self, policy: &VerifyPolicy, strict: bool) -> Result<(), NodeAuthResult> {
+        self.fields().verify_posture(policy, strict)
+    }
+
+    fn fields(&self) -> ReportFields<'_> {
+        ReportFields {
+            cpu_svn: &self.cpu_svn,
+            misc_select: self.misc_select,
+            attributes: self.attributes,
+            mr_enclave: &self.mr_enclave,
+            mr_signer: &self.mr_signer,
+            isv_prod_id: self.isv_prod_id,
+            isv_svn: self.isv_svn,
+        }
+    }
+}
+
+/// The fields of a report that verification checks, borrowed from either an
+/// `SgxEnclaveReport` or an `SgxEnclaveReportRef`, so that both are verified by
+/// the same code and neither is copied for it
+struct ReportFields<'r> {
+    cpu_svn: &'r [u8; 16],
+    misc_select: u32,
+    attributes: SgxReportAttributes,
+    mr_enclave: &'r [u8; 32],
+    mr_signer: &'r [u8; 32],
+    isv_prod_id: u16,
+    isv_svn: u16,
+}
+
+impl ReportFields<'_> {
+    fn verify_posture(&self, policy: &VerifyPolicy, strict: bool) -> Result<(), NodeAuthResult> {
+        let self_report = get_cached_report()?;
+
+        match &policy.mr_enclave {
+            Some(matcher) if !matcher.matches(self.mr_enclave) => {
+                warn!(
+                    "mr_enclave: received: {:?} \n not accepted by: {:?}",
+                    self.mr_enclave, matcher
+                );
+                return Err(NodeAuthResult::MrEnclaveMismatch);
+            }
+            None if !ct_eq(self.mr_enclave, &self_report.body.mr_enclave.m) => {
+                warn!(
+                    "mr_enclave: received: {:?} \n expected: {:?}",
+                    self.mr_enclave, self_report.body.mr_enclave.m
+                );
+                return Err(NodeAuthResult::MrEnclaveMismatch);
+            }
+            _ => {}
+        }
+
+        if !policy.allowed_signers.is_empty() {
+            self.verify_signer(&policy.allowed_signers)?;
+        }
+
+        // production nodes never accept 32-bit or debug enclaves, whatever the
+        // policy
+        if strict && (policy.require_64bit || cfg!(feature = "production")) {
+            self.verify_64bit()?;
+        }
+
+        if strict && (policy.reject_debug || cfg!(feature = "production")) {
+            self.verify_not_debug()?;
+        }
+
+        // debug mode is only a matter of posture, so it's ignored when permissive
+        let expected = policy.attributes.unwrap_or(SgxReportAttributes {
+            flags: self_report.body.attributes.flags,
+            xfrm: self_report.body.attributes.xfrm,
+        });
+        let flags_mask = if strict { !0 } else { !SGX_FLAGS_DEBUG };
+        if !self.attributes.ct_eq(&expected, flags_mask) {
+            warn!(
+                "Got a report with different attributes than expected: {:?}",
+                self.attributes
+            );
+            return Err(NodeAuthResult::MrEnclaveMismatch);
+        }
+
+        if let Some(expected) = policy.isv_prod_id {
+            self.verify_prod_id(expected)?;
+        }
+
+        if let Some(expected) = policy.misc_select {
+            self.verify_misc_select(expected)?;
+        }
+
+        if self.isv_svn < policy.min_isv_svn {
+            warn!(
+                "isv_svn of report is too low: {} (minimum {})",
+                self.isv_svn, policy.min_isv_svn
+            );
+            return Err(NodeAuthResult::IsvSvnTooLow);
+        }
+
+        if let Some(min) = policy.min_cpu_svn {
+            let missing = CpuSvn::from(*self.cpu_svn).missing_updates_vs(&CpuSvn(min));
+            if !missing.is_empty() {
+                warn!(
+                    "cpu_svn {:?} is below the minimum {:?} in components {:?}",
+                    self.cpu_svn, min, missing
+                );
+                return Err(NodeAuthResult::CpuSvnTooLow);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn verify_64bit(&self) -> Result<(), NodeAuthResult> {
+        if !self.attributes.is_64bit() {
+            warn!(
+                "Got a report of an enclave that isn't 64-bit: {:?}",
+                self.attributes
+            );
+            return Err(NodeAuthResult::Not64Bit);
+        }
+
+        Ok(())
+    }
+
+    fn verify_not_debug(&self) -> Result<(), NodeAuthResult> {
+        if self.attributes.flag_set().contains(AttributeFlags::DEBUG) {
+            warn!("Got a report of an enclave running in debug mode");
+            return Err(NodeAuthResult::DebugEnclaveNotAllowed);
+        }
+
+        Ok(())
+    }
+
+    fn verify_signer(&self, allowed_signers: &[[u8; 32]]) -> Result<(), NodeAuthResult> {
+        let allowed = allowed_signers
+            .iter()
+            .fold(false, |found, signer| found | ct_eq(signer, self.mr_signer));
+        if !allowed {
+            warn!(
+                "Got a report signed by an untrusted key: {:?}",
+                self.mr_signer
+            );
+            return Err(NodeAuthResult::MrSignerMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn verify_prod_id(&self, expected: u16) -> Result<(), NodeAuthResult> {
+        if self.isv_prod_id != expected {
+            warn!(
+                "isv_prod_id of report: {} (expected {})",
+                self.isv_prod_id, expected
+            );
+            return Err(NodeAuthResult::IsvProdIdMismatch);
+        }
+
+        Ok(())
+    }
+
+    fn verify_misc_select(&self, expected: u32) -> Result<(), NodeAuthResult> {
+        if self.misc_select != expected {
+            warn!(
+                "misc_select of report: {:#x} (expected {:#x})",
+                self.misc_select, expected
+            );
+            return Err(NodeAuthResult::MiscSelectMismatch);
+        }
+
+        Ok(())
+    }
+}
+
+/// Borrowed view of a serialized `SgxEnclaveReport`. The fields are read from
+/// the input when accessed rather than copied, for read-only passes over many
+/// reports.
+#[derive(Clone, Copy)]
+pub struct SgxEnclaveReportRef<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SgxEnclaveReportRef<'a> {
+    /// Same as `SgxEnclaveReport::parse_from`: reports longer than
+    /// `REPORT_BODY_LEN` are accepted, the rest being `extra`
+    pub fn parse_from(bytes: &'a [u8]) -> Result<Self, Error> {
+        if bytes.len() < REPORT_BODY_LEN {
+            error!(
+                "Enclave report parsing error - bad report size: got {}, expected at least {}",
+                bytes.len(),
+                REPORT_BODY_LEN
+            );
+            return Err(Error::ReportTruncated {
+                expected: REPORT_BODY_LEN,
+                got: bytes.len(),
+            });
+        }
+
+        Ok(Self { bytes })
+    }
+
+    // the size was checked by parse_from, so fields are always in bounds
+    fn field<const N: usize>(&self, offset: usize) -> &'a [u8; N] {
+        <&[u8; N]>::try_from(&self.bytes[offset..offset + N]).unwrap()
+    }
+
+    pub fn cpu_svn(&self) -> &'a [u8; 16] {
+        self.field(0)
+    }
+
+    pub fn misc_select(&self) -> u32 {
+        u32::from_le_bytes(*self.field(16))
+    }
+
+    pub fn isv_ext_prod_id(&self) -> &'a [u8; 16] {
+        self.field(32)
+    }
+
+    pub fn attributes(&self) -> SgxReportAttributes {
+        SgxReportAttributes {
+            flags: u64::from_le_bytes(*self.field(48)),
+            xfrm: u64::from_le_bytes(*self.field(56)),
+        }
+    }
+
+    pub fn mr_enclave(&self) -> &'a [u8; 32] {
+        self.field(64)
+    }
+
+    pub fn mr_signer(&self) -> &'a [u8; 32] {
+        self.field(128)
+    }
+
+    pub fn config_id(&self) -> &'a [u8; 64] {
+        self.field(192)
+    }
+
+    pub fn isv_prod_id(&self) -> u16 {
+        u16::from_le_bytes(*self.field(256))
+    }
+
+    pub fn isv_svn(&self) -> u16 {
+        u16::from_le_bytes(*self.field(258))
+    }
+
+    pub fn config_svn(&self) -> u16 {
+        u16::from_le_bytes(*self.field(260))
+    }
+
+    pub fn isv_family_id(&self) -> &'a [u8; 16] {
+        self.field(304)
+    }
+
+    pub fn report_data(&self) -> &'a [u8; 64] {
+        self.field(320)
+    }
+
+    pub fn extra(&self) -> &'a [u8] {
+        &self.bytes[REPORT_BODY_LEN..]
+    }
+
+    /// Copy the report out of the input into an `SgxEnclaveReport`
+    pub fn to_report(&self) -> SgxEnclaveReport {
+        SgxEnclaveReport {
+            cpu_svn: *self.cpu_svn(),
+            misc_select: self.misc_select(),
+            isv_ext_prod_id: *self.isv_ext_prod_id(),
+            attributes: self.attributes(),
+            mr_enclave: *self.mr_enclave(),
+            mr_signer: *self.mr_signer(),
+            isv_prod_id: self.isv_prod_id(),
+            isv_svn: self.isv_svn(),
+            config_id: *self.config_id(),
+            config_svn: self.config_svn(),
+            isv_family_id: *self.isv_family_id(),
+            report_data: *self.report_data(),
+            extra: self.extra().to_vec(),
+        }
+    }
+
+    /// Same as `SgxEnclaveReport::verify_with_policy`, checking the fields in
+    /// place rather than copying the report
+    pub fn verify_with_policy(&self, policy: &VerifyPolicy) -> Result<(), NodeAuthResult> {
+        self.fields().verify_posture(policy, true)
+    }
+
+    /// Same as `SgxEnclaveReport::verify`
+    pub fn verify(&self) -> Result<(), NodeAuthResult> {
+        self.verify_with_policy(&VerifyPolicy::default())
+    }
+
+    fn fields(&self) -> ReportFields<'a> {
+        ReportFields {
+            cpu_svn: self.cpu_svn(),
+            misc_select: self.misc_select(),
+            attributes: self.attributes(),
+            mr_enclave: self.mr_enclave(),
+            mr_signer: self.mr_signer(),
+            isv_prod_id: self.isv_prod_id(),
+            isv_svn: self.isv_svn(),
+        }
+    }
+}
+
+/// Builds an `SgxEnclaveReport` field by field, starting from an all-zero
+/// report, for tests that need small variations of a report
+#[cfg(feature = "test")]
+pub struct SgxEnclaveReportBuilder {
+    report: SgxEnclaveReport,
+}
+
+#[cfg(feature = "test")]
+impl SgxEnclaveReport {
+    pub fn builder() -> SgxEnclaveReportBuilder {
+        SgxEnclaveReportBuilder {
+            report: SgxEnclaveReport {
+                cpu_svn: [0; 16],
+                misc_select: 0,
+                isv_ext_prod_id: [0; 16],
+                attributes: SgxReportAttributes::default(),
+                mr_enclave: [0; 32],
+                mr_signer: [0; 32],
+                isv_prod_id: 0,
+                isv_svn: 0,
+                config_id: [0; 64],
+                config_svn: 0,
+                isv_family_id: [0; 16],
+                report_data: [0; 64],
+                extra: vec![],
+            },
+        }
+    }
+}
+
+#[cfg(feature = "test")]
+impl SgxEnclaveReportBuilder {
+    pub fn cpu_svn(mut self, cpu_svn: [u8; 16]) -> Self {
+        self.report.cpu_svn = cpu_svn;
+        self
+    }
+
+    pub fn misc_select(mut self, misc_select: u32) -> Self {
+        self.report.misc_select = misc_select;
+        self
+    }
+
+    pub fn attributes(mut self, attributes: SgxReportAttributes) -> Self {
+        self.report.attributes = attributes;
+        self
+    }
+
+    pub fn mr_enclave(mut self, mr_enclave: [u8; 32]) -> Self {
+        self.report.mr_enclave = mr_enclave;
+        self
+    }
+
+    pub fn mr_signer(mut self, mr_signer: [u8; 32]) -> Self {
+        self.report.mr_signer = mr_signer;
+        self
+    }
+
+    pub fn isv_prod_id(mut self, isv_prod_id: u16) -> Self {
+        self.report.isv_prod_id = isv_prod_id;
+        self
+    }
+
+    pub fn isv_svn(mut self, isv_svn: u16) -> Self {
+        self.report.isv_svn = isv_svn;
+        self
+    }
+
+    pub fn report_data(mut self, report_data: [u8; 64]) -> Self {
+        self.report.report_data = report_data;
+        self
+    }
+
+    pub fn build(self) -> SgxEnclaveReport {
+        self.report
+    }
+}
+
+/// Compare two byte strings without short-circuiting on the first differing
+/// byte, so the time taken doesn't depend on where they differ
+fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter()
+        .zip(b.iter())
+        .fold(0u8, |acc, (x, y)| acc | (x ^ y))
+        == 0
+}
+
+/// Whether this build verifies reports against real hardware attestation. In
+/// software mode the enclave's own report is empty, so verification is only
+/// a stand-in and the host should say so.
+pub const fn verification_available() -> bool {
+    cfg!(feature = "SGX_MODE_HW")
+}
+
+/// Public key of a node, as bound into the `report_data` of its enclave report
+pub type NodeAuthPublicKey = [u8; PUBLIC_KEY_SIZE];
+
+/// Decides whether a measurement (e.g. `mr_enclave`) is acceptable
+pub trait MeasurementMatcher: std::fmt::Debug {
+    fn matches(&self, actual: &[u8; 32]) -> bool;
+}
+
+/// Accepts a single measurement
+#[derive(Debug)]
+pub struct Exact(pub [u8; 32]);
+
+impl MeasurementMatcher for Exact {
+    fn matches(&self, actual: &[u8; 32]) -> bool {
+        ct_eq(&self.0, actual)
+    }
+}
+
+/// Accepts any measurement in the allowlist
+#[derive(Debug)]
+pub struct AnyOf(pub Vec<[u8; 32]>);
+
+impl MeasurementMatcher for AnyOf {
+    fn matches(&self, actual: &[u8; 32]) -> bool {
+        // check every entry, so the position of a match doesn't show in timing
+        self.0
+            .iter()
+            .fold(false, |found, allowed| found | ct_eq(allowed, actual))
+    }
+}
+
+/// Accepts any measurement that wasn't revoked
+#[derive(Debug)]
+pub struct NotIn(pub HashSet<[u8; 32]>);
+
+impl MeasurementMatcher for NotIn {
+    fn matches(&self, actual: &[u8; 32]) -> bool {
+        !self.0.contains(actual)
+    }
+}
+
+/// Checks applied when verifying the report of another node, in addition to
+/// matching the attributes of this enclave
+#[derive(Debug, Default)]
+pub struct VerifyPolicy {
+    /// Lowest enclave security version number that is accepted
+    pub min_isv_svn: u16,
+    /// Maximum age in seconds of an attestation report
+    pub max_report_age: Option<u64>,
+    /// Accepted `mr_enclave` values. When not set, only the measurement of
+    /// this enclave is accepted
+    pub mr_enclave: Option<Box<dyn MeasurementMatcher>>,
+    /// Expected attributes. When not set, the attributes of this enclave are
+    /// expected
+    pub attributes: Option<SgxReportAttributes>,
+    /// Time by which I/O bound verification steps must be done. Checks that
+    /// are pure computation always run to completion
+    pub deadline: Option<Instant>,
+    /// Reject reports of enclaves that don't run in 64-bit mode. Always done in
+    /// production builds
+    pub require_64bit: bool,
+    /// Reject reports of enclaves running in debug mode. Always done in
+    /// production builds
+    pub reject_debug: bool,
+    /// Expected `isv_prod_id`. When not set, any product id is accepted
+    pub isv_prod_id: Option<u16>,
+    /// Accepted `mr_signer` values. When empty, the signer isn't checked
+    pub allowed_signers: Vec<[u8; 32]>,
+    /// Lowest `cpu_svn` that is accepted, compared component by component
+    pub min_cpu_svn: Option<[u8; 16]>,
+    /// Expected `misc_select`. When not set, any MISC features are accepted
+    pub misc_select: Option<u32>,
+    /// Expected key id of full reports (`SgxReport`). When not set, any key id
+    /// is accepted
+    pub key_id: Option<[u8; 32]>,
+}
+
+/// A check of `SgxEnclaveReport::verify_report` that didn't pass
+#[derive(Debug, PartialEq)]
+pub struct FailedCheck {
+    pub check: &'static str,
+    pub got: String,
+    pub expected: String,
+}
+
+/// Outcome of `SgxEnclaveReport::verify_report`, meant to be shared by
+/// operators when asking for support
+#[derive(Debug)]
+pub struct VerifyReport {
+    pub result: Result<(), NodeAuthResult>,
+    pub failed_checks: Vec<FailedCheck>,
+    pub advisory_ids: Vec<String>,
+    /// Measurements of the enclave running this code
+    pub expected_mr_enclave: [u8; 32],
+    pub expected_mr_signer: [u8; 32],
+}
+
+impl VerifyReport {
+    /// The report as JSON. `report_data` holds the public key of the node, and
+    /// is never included.
+    pub fn to_support_json(&self) -> String {
+        let failed_checks: Vec<Value> = self
+            .failed_checks
+            .iter()
+            .map(|c| {
+                serde_json::json!({
+                    "check": c.check,
+                    "got": c.got,
+                    "expected": c.expected,
+                })
+            })
+            .collect();
+
+        serde_json::json!({
+            "outcome": match &self.result {
+                Ok(()) => "success".to_string(),
+                Err(e) => format!("failure: {}", e),
+            },
+            "failed_checks": failed_checks,
+            "advisory_ids": self.advisory_ids,
+            "expected_measurements": {
+                "mr_enclave": hex::encode(self.expected_mr_enclave),
+                "mr_signer": hex::encode(self.expected_mr_signer),
+            },
+            "report_data": "<redacted>",
+        })
+        .to_string()
+    }
+}
+
+/// Outcome of `SgxEnclaveReport::verify_detailed`
+#[derive(Debug, PartialEq)]
+pub struct VerifyDetails {
+    pub result: Result<(), NodeAuthResult>,
+    /// Whether the report passes when the production posture isn't enforced
+    pub would_pass_permissive: bool,
+}
+
+fn check_deadline(deadline: Option<Instant>) -> Result<(), NodeAuthResult> {
+    match deadline {
+        Some(deadline) if Instant::now() > deadline => {
+            warn!("Verification deadline exceeded");
+            Err(NodeAuthResult::DeadlineExceeded)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Fields of a verified report, filled by `verify_into` for the caller
+#[repr(C)]
+#[derive(Debug, Default, PartialEq)]
+pub struct VerifyReportSummary {
+    pub mr_enclave: [u8; 32],
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub isv_svn: u16,
+    pub owner_key: NodeAuthPublicKey,
+}
+
+/// Verify a serialized enclave report, writing its details into `out`. The
+/// report is checked in place through `SgxEnclaveReportRef` and the results are
+/// plain arrays and integers, so nothing is allocated, which keeps bulk
+/// verification cheap. `out` is only written if the report verified.
+pub fn verify_into(
+    report_bytes: &[u8; REPORT_BODY_LEN],
+    policy: &VerifyPolicy,
+    out: &mut VerifyReportSummary,
+) -> NodeAuthResult {
+    // a full report body, so there is no length to check
+    let report = SgxEnclaveReportRef {
+        bytes: report_bytes,
+    };
+
+    if let Err(e) = report.verify_with_policy(policy) {
+        return e;
+    }
+
+    out.mr_enclave = *report.mr_enclave();
+    out.mr_signer = *report.mr_signer();
+    out.isv_prod_id = report.isv_prod_id();
+    out.isv_svn = report.isv_svn();
+    out.owner_key
+        .copy_from_slice(&report.report_data()[..PUBLIC_KEY_SIZE]);
+
+    NodeAuthResult::Success
+}
+
+/// Run the standard checks on `report` and the attestation vouching for it,
+/// then hand both to `hook` so operators can layer their own requirements on
+/// top (e.g. FMSPC based restrictions). An error from the hook is returned as is.
+///
+/// The hook may reach out of the enclave, so it is subject to the deadline of
+/// `policy`: it isn't called if the deadline already passed, and its result is
+/// discarded if it ran past the deadline.
+pub fn verify_with_hook<F>(
+    report: &SgxEnclaveReport,
+    attestation: &AttestationReport,
+    policy: &VerifyPolicy,
+    hook: F,
+) -> Result<(), NodeAuthResult>
+where
+    F: Fn(&SgxEnclaveReport, &AttestationReport) -> Result<(), NodeAuthResult>,
+{
+    report.verify_with_policy(policy)?;
+
+    if !ct_eq(
+        &attestation.sgx_quote_body.isv_enclave_report.report_data,
+        &report.report_data,
+    ) {
+        warn!("Attestation report was not generated for the provided enclave report");
+        return Err(NodeAuthResult::ReportDataMismatch);
+    }
+
+    check_deadline(policy.deadline)?;
+    let result = hook(report, attestation);
+    check_deadline(policy.deadline)?;
+
+    result
+}
+
+/// Owner keys of the peers that were already verified. The same key showing up
+/// for two distinct peers means one of them is impersonating the other.
+#[derive(Debug, Default)]
+pub struct SeenKeys(HashSet<NodeAuthPublicKey>);
+
+impl SeenKeys {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn contains(&self, key: &NodeAuthPublicKey) -> bool {
+        self.0.contains(key)
+    }
+
+    /// Returns false if the key was already seen
+    pub fn insert(&mut self, key: NodeAuthPublicKey) -> bool {
+        self.0.insert(key)
+    }
+}
+
+/// Verify the reports of distinct peers, one result per report. A report whose
+/// owner key was already seen (in this batch or in `seen`) is rejected with
+/// `DuplicateKey`, and keys of reports that verified are added to `seen`.
+pub fn verify_batch(
+    reports: &[SgxEnclaveReport],
+    policy: &VerifyPolicy,
+    seen: &mut SeenKeys,
+) -> Vec<Result<NodeAuthPublicKey, NodeAuthResult>> {
+    reports
+        .iter()
+        .map(|report| {
+            report.verify_with_policy(policy)?;
+
+            let key = report.get_owner_key_unchecked();
+            if !seen.insert(key) {
+                warn!("Got the same owner key from distinct peers: {:?}", key);
+                return Err(NodeAuthResult::DuplicateKey);
+            }
+
+            Ok(key)
+        })
+        .collect()
+}
+
+/// Verify a set of peer reports, and succeed only if at least `k` of them
+/// pass verification. Returns the public keys bound to the verified reports,
+/// or the error of the first report that failed if the threshold wasn't met.
+pub fn verify_threshold(
+    reports: &[SgxEnclaveReport],
+    policy: &VerifyPolicy,
+    k: usize,
+) -> Result<Vec<NodeAuthPublicKey>, NodeAuthResult> {
+    let mut verified = vec![];
+    let mut first_error = None;
+
+    for report in reports {
+        match report.verify_with_policy(policy) {
+            Ok(()) => verified.push(report.get_owner_key_unchecked()),
+            Err(e) => {
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    if verified.len() < k {
+        warn!(
+            "Only {} out of {} reports verified, at least {} are required",
+            verified.len(),
+            reports.len(),
+            k
+        );
+        return Err(first_error.unwrap_or(NodeAuthResult::InvalidInput));
+    }
+
+    Ok(verified)
+}
+
+/// Expected identity of the Quoting Enclave, as published by Intel in the QE
+/// Identity collateral
+#[derive(Debug, Clone, PartialEq)]
+pub struct QeIdentity {
+    pub mr_signer: [u8; 32],
+    pub isv_prod_id: u16,
+    pub min_isv_svn: u16,
+}
+
+/// Verify that the QE report found in the signature data of a DCAP quote
+/// (`SgxQuote::qe_report`) was generated by the Quoting Enclave in `identity`
+pub fn verify_qe_identity(
+    qe_report: &SgxEnclaveReport,
+    identity: &QeIdentity,
+) -> Result<(), NodeAuthResult> {
+    qe_report.verify_signer(&[identity.mr_signer])?;
+    qe_report.verify_prod_id(identity.isv_prod_id)?;
+
+    if qe_report.isv_svn < identity.min_isv_svn {
+        warn!(
+            "isv_svn of QE report is too low: {} (minimum {})",
+            qe_report.isv_svn, identity.min_isv_svn
+        );
+        return Err(NodeAuthResult::IsvSvnTooLow);
+    }
+
+    Ok(())
+}
+
+/// Attributes of the enclave (`sgx_attributes_t`)
+#[derive(Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "report-serde", derive(Serialize, Deserialize))]
+pub struct SgxReportAttributes {
+    pub flags: u64,
+    pub xfrm: u64,
+}
+
+impl std::fmt::Debug for SgxReportAttributes {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SgxReportAttributes")
+            .field(
+                "flags",
+                &format_args!("{:?} ({:#x})", self.flag_set(), self.flags),
+            )
+            .field("xfrm", &self.xfrm)
+            .finish()
+    }
+}
+
+/// The enclave was initialized by `EINIT` (`SGX_FLAGS_INITTED`)
+pub const SGX_FLAGS_INITTED: u64 = 0x0000_0000_0000_0001;
+/// The enclave runs in debug mode (`SGX_FLAGS_DEBUG`)
+pub const SGX_FLAGS_DEBUG: u64 = 0x0000_0000_0000_0002;
+/// The enclave runs in 64-bit mode (`SGX_FLAGS_MODE64BIT`)
+pub const SGX_FLAGS_MODE64BIT: u64 = 0x0000_0000_0000_0004;
+/// The enclave has access to the provisioning key (`SGX_FLAGS_PROVISION_KEY`)
+pub const SGX_FLAGS_PROVISION_KEY: u64 = 0x0000_0000_0000_0010;
+/// The enclave has access to the EINIT token key (`SGX_FLAGS_EINITTOKEN_KEY`)
+pub const SGX_FLAGS_EINITTOKEN_KEY: u64 = 0x0000_0000_0000_0020;
+/// The enclave uses the key separation and sharing fields (`SGX_FLAGS_KSS`)
+pub const SGX_FLAGS_KSS: u64 = 0x0000_0000_0000_0080;
+/// The flags that matter for the security of an enclave, which must match
+/// even when other attributes are allowed to differ
+pub const SECURITY_FLAGS_MASK: u64 = SGX_FLAGS_DEBUG | SGX_FLAGS_MODE64BIT;
+
+bitflags! {
+    /// Decoded `SgxReportAttributes::flags`
+    pub struct AttributeFlags: u64 {
+        const INITTED = SGX_FLAGS_INITTED;
+        const DEBUG = SGX_FLAGS_DEBUG;
+        const MODE64BIT = SGX_FLAGS_MODE64BIT;
+        const PROVISION_KEY = SGX_FLAGS_PROVISION_KEY;
+        const EINITTOKEN_KEY = SGX_FLAGS_EINITTOKEN_KEY;
+        const KSS = SGX_FLAGS_KSS;
+    }
+}
+
+bitflags! {
+    /// Decoded `SgxReportAttributes::xfrm`, the XSAVE feature set (XCR0) the
+    /// enclave runs with
+    pub struct XfrmFeatures: u64 {
+        const X87 = 1 << 0;
+        const SSE = 1 << 1;
+        const AVX = 1 << 2;
+        const MPX_BNDREGS = 1 << 3;
+        const MPX_BNDCSR = 1 << 4;
+        const AVX512_OPMASK = 1 << 5;
+        const AVX512_ZMM_HI256 = 1 << 6;
+        const AVX512_HI16_ZMM = 1 << 7;
+        const PKRU = 1 << 9;
+        const AMX_TILECFG = 1 << 17;
+        const AMX_TILEDATA = 1 << 18;
+        /// All the state components needed to use AVX-512
+        const AVX512 = Self::AVX512_OPMASK.bits
+            | Self::AVX512_ZMM_HI256.bits
+            | Self::AVX512_HI16_ZMM.bits;
+    }
+}
+
+impl SgxReportAttributes {
+    pub fn is_init(&self) -> bool {
+        self.flags & SGX_FLAGS_INITTED != 0
+    }
+
+    pub fn is_debug(&self) -> bool {
+        self.flags & SGX_FLAGS_DEBUG != 0
+    }
+
+    pub fn is_64bit(&self) -> bool {
+        self.flags & SGX_FLAGS_MODE64BIT != 0
+    }
+
+    pub fn is_provision_key(&self) -> bool {
+        self.flags & SGX_FLAGS_PROVISION_KEY != 0
+    }
+
+    /// The known flags that are set. Unknown bits are dropped, see `flags` for
+    /// the raw value
+    pub fn flag_set(&self) -> AttributeFlags {
+        AttributeFlags::from_bits_truncate(self.flags)
+    }
+
+    /// The known XSAVE features that are enabled. Unknown bits are dropped,
+    /// see `xfrm` for the raw value
+    pub fn xfrm_features(&self) -> XfrmFeatures {
+        XfrmFeatures::from_bits_truncate(self.xfrm)
+    }
+
+    pub fn has_avx(&self) -> bool {
+        self.xfrm_features().contains(XfrmFeatures::AVX)
+    }
+
+    /// Whether every state component of AVX-512 is enabled
+    pub fn has_avx512(&self) -> bool {
+        self.xfrm_features().contains(XfrmFeatures::AVX512)
+    }
+
+    /// Constant-time equality, ignoring the flags outside of `flags_mask`
+    fn ct_eq(&self, other: &Self, flags_mask: u64) -> bool {
+        self.ct_eq_masked(other, flags_mask, !0)
+    }
+
+    /// Constant-time equality, ignoring the flags outside of `flags_mask` and
+    /// the XSAVE features outside of `xfrm_mask`
+    fn ct_eq_masked(&self, other: &Self, flags_mask: u64, xfrm_mask: u64) -> bool {
+        let flags = (self.flags ^ other.flags) & flags_mask;
+        let xfrm = (self.xfrm ^ other.xfrm) & xfrm_mask;
+        ct_eq(&(flags | xfrm).to_le_bytes(), &[0u8; 8])
+    }
+}
+
+/// Security version number of the host CPU, as found in `SgxEnclaveReport::cpu_svn`.
+/// The SVN is made of independent components (microcode, firmware patches,
+/// etc.) so it is not a single number, and two values can only be compared
+/// component by component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CpuSvn(pub [u8; 16]);
+
+impl From<[u8; 16]> for CpuSvn {
+    fn from(svn: [u8; 16]) -> Self {
+        CpuSvn(svn)
+    }
+}
+
+impl CpuSvn {
+    /// Indices of the SVN components that are lower than in `baseline`, i.e.
+    /// the updates this platform is missing compared to a known-good platform
+    pub fn missing_updates_vs(&self, baseline: &CpuSvn) -> Vec<usize> {
+        self.0
+            .iter()
+            .zip(baseline.0.iter())
+            .enumerate()
+            .filter(|(_, (svn, min))| svn < min)
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Minimum `cpu_svn` expected for enclaves from a given `isv_svn` onwards.
+/// The entry with the highest `isv_svn` not above the one of a report applies.
+#[derive(Debug, Default)]
+pub struct SvnMatrix(pub BTreeMap<u16, CpuSvn>);
+
+impl SvnMatrix {
+    pub fn min_cpu_svn(&self, isv_svn: u16) -> Option<&CpuSvn> {
+        self.0
+            .range(..=isv_svn)
+            .next_back()
+            .map(|(_, cpu_svn)| cpu_svn)
+    }
+}
+
+/// A full report as generated by `EREPORT` (`sgx_report_t`), i.e. the
+/// `SgxEnclaveReport` body followed by the key id and the MAC over the body.
+/// This is what enclaves on the same platform exchange during local
+/// attestation.
+pub struct SgxReport {
+    /// Body of the report
+    pub body: SgxEnclaveReport,
+    /// Key wear-out protection value used to derive the report key for the MAC
+    pub key_id: [u8; 32],
+    /// CMAC over the report body, using the report key of the target enclave
+    pub mac: [u8; 16],
+}
+
+impl SgxReport {
+    /// Parse bytes of a full `sgx_report_t` into `SgxReport`.
+    pub fn parse_from(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < SGX_REPORT_LEN {
+            warn!(
+                "Report parsing error - bad report size: got {}, expected {}",
+                bytes.len(),
+                SGX_REPORT_LEN
+            );
+            return Err(Error::ReportTruncated {
+                expected: SGX_REPORT_LEN,
+                got: bytes.len(),
+            });
+        }
+        if bytes.len() > SGX_REPORT_LEN {
+            warn!(
+                "Report parsing error - bad report size: got {}, expected {}",
+                bytes.len(),
+                SGX_REPORT_LEN
+            );
+            return Err(Error::ReportTrailingBytes(bytes.len() - SGX_REPORT_LEN));
+        }
+
+        // off 0, size 384
+        let body = SgxEnclaveReport::parse_from(&bytes[..REPORT_BODY_LEN])?;
+
+        // off 384, size 32
+        let key_id = <[u8; 32]>::try_from(&bytes[REPORT_BODY_LEN..REPORT_BODY_LEN + 32])?;
+
+        // off 416, size 16
+        let mac = <[u8; 16]>::try_from(&bytes[REPORT_BODY_LEN + 32..])?;
+
+        Ok(Self { body, key_id, mac })
+    }
+
+    /// Verify that the report was MACed using the expected key id. Reports that
+    /// are checked across enclaves must agree on the key id, otherwise the MAC
+    /// was computed with a different report key.
+    pub fn verify_key_id(&self, expected_key_id: &[u8; 32]) -> Result<(), NodeAuthResult> {
+        if !ct_eq(&self.key_id, expected_key_id) {
+            warn!(
+                "Report key id mismatch. received: {:?} \n expected: {:?}",
+                self.key_id, expected_key_id
+            );
+            return Err(NodeAuthResult::KeyIdMismatch);
+        }
+
+        Ok(())
+    }
+
+    /// Verify the body of the report with `policy`, and its key id if the
+    /// policy requires one
+    pub fn verify_with_policy(&self, policy: &VerifyPolicy) -> Result<(), NodeAuthResult> {
+        if let Some(expected_key_id) = &policy.key_id {
+            self.verify_key_id(expected_key_id)?;
+        }
+
+        self.body.verify_with_policy(policy)
+    }
+}
+
+/// SGX Quote structure version
+#[derive(Debug, PartialEq)]
+#[allow(dead_code)]
+pub enum SgxQuoteVersion {
+    /// EPID quote version
+    V1(SgxEpidQuoteSigType),
+    /// EPID quote version
+    V2(SgxEpidQuoteSigType),
+    /// ECDSA quote version
+    V3(SgxEcdsaQuoteAkType),
+}
+
+/// Intel EPID attestation signature type
+#[derive(Debug, PartialEq)]
+#[allow(dead_code)]
+pub enum SgxEpidQuoteSigType {
+    Unlinkable,
+    Linkable,
+}
+
+impl SgxEpidQuoteSigType {
+    /// Map the signature type field of an EPID quote to its variant
+    pub fn from_u16(sig_type: u16) -> Result<Self, Error> {
+        match sig_type {
+            0 => Ok(SgxEpidQuoteSigType::Unlinkable),
+            1 => Ok(SgxEpidQuoteSigType::Linkable),
+            _ => {
+                warn!("Invalid EPID quote signature type: {}", sig_type);
+                Err(Error::ReportParseError)
+            }
+        }
+    }
+
+    /// The signature type field of an EPID quote for this variant
+    pub fn as_u16(&self) -> u16 {
+        match self {
+            SgxEpidQuoteSigType::Unlinkable => 0,
+            SgxEpidQuoteSigType::Linkable => 1,
+        }
+    }
+}
+
+/// ID of an Intel EPID group, as found in the header of EPID quotes
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EpidGroupId(pub u32);
+
+impl EpidGroupId {
+    pub fn as_u32(&self) -> u32 {
+        self.0
+    }
+}
+
+// Intel's tooling (and the IAS SigRL API) writes group ids as 8 hex digits
+impl std::fmt::Debug for EpidGroupId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:08x}", self.0)
+    }
+}
+
+/// ECDSA attestation key type
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum SgxEcdsaQuoteAkType {
+    /// ECDSA-256-with-P-256 curve
+    P256_256,
+    /// ECDSA-384-with-P-384 curve
+    P384_384,
+}
+
+/// SGX Quote status
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
+pub enum SgxQuoteStatus {
+    /// EPID signature of the ISV enclave QUOTE was verified correctly and the
+    /// TCB level of the SGX platform is up-to-date.
+    OK,
+    /// EPID signature of the ISV enclave QUOTE was invalid. The content of the
+    /// QUOTE is not trustworthy.
+    ///
+    /// For DCAP, the signature over the application report is invalid.
+    SignatureInvalid,
+    /// The EPID group has been revoked. When this value is returned, the
+    /// revocation Reason field of the Attestation Verification Report will
+    /// contain revocation reason code for this EPID group as reported in the
+    /// EPID Group CRL. The content of the QUOTE is not trustworthy.
+    GroupRevoked,
+    /// The EPID private key used to sign the QUOTE has been revoked by
+    /// signature. The content of the QUOTE is not trustworthy.
+    SignatureRevoked,
+    /// The EPID private key used to sign the QUOTE has been directly revoked
+    /// (not by signature). The content of the QUOTE is not trustworthy.
+    ///
+    /// For DCAP, the attestation key or platform has been revoked.
+    KeyRevoked,
+    /// SigRL version in ISV enclave QUOTE does not match the most recent
+    /// version of the SigRL. In rare situations, after SP retrieved the SigRL
+    /// from IAS and provided it to the platform, a newer version of the SigRL
+    /// is madeavailable. As a result, the Attestation Verification Report will
+    /// indicate SIGRL_VERSION_MISMATCH. SP can retrieve the most recent version
+    /// of SigRL from the IAS and request the platform to perform remote
+    /// attestation again with the most recent version of SigRL. If the platform
+    /// keeps failing to provide a valid QUOTE matching with the most recent
+    /// version of the SigRL, the content of the QUOTE is not trustworthy.
+    SigrlVersionMismatch,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly,
+    /// but the TCB level of SGX platform is outdated (for further details see
+    /// Advisory IDs). The platform has not been identified as compromised and
+    /// thus it is not revoked. It is up to the Service Provider to decide
+    /// whether or not to trust the content of the QUOTE, andwhether or not to
+    /// trust the platform performing the attestation to protect specific
+    /// sensitive information.
+    GroupOutOfDate,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly,
+    /// but additional configuration of SGX platform may be needed(for further
+    /// details see Advisory IDs). The platform has not been identified as
+    /// compromised and thus it is not revoked. It is up to the Service Provider
+    /// to decide whether or not to trust the content of the QUOTE, and whether
+    /// or not to trust the platform performing the attestation to protect
+    /// specific sensitive information.
+    ///
+    /// For DCAP, The Quote verification passed and the platform is patched to
+    /// the latest TCB level but additional configuration of the SGX
+    /// platform may be needed.
+    ConfigurationNeeded,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly
+    /// but due to certain issues affecting the platform, additional SW
+    /// Hardening in the attesting SGX enclaves may be needed.The relying party
+    /// should evaluate the potential risk of an attack leveraging the relevant
+    /// issues on the attesting enclave, and whether the attesting enclave
+    /// employs adequate software hardening to mitigate the risk.
+    SwHardeningNeeded,
+    /// The EPID signature of the ISV enclave QUOTE has been verified correctly
+    /// but additional configuration for the platform and SW Hardening in the
+    /// attesting SGX enclaves may be needed. The platform has not been
+    /// identified as compromised and thus it is not revoked. It is up to the
+    /// Service Provider to decide whether or not to trust the content of the
+    /// QUOTE. The relying party should also evaluate the potential risk of an
+    /// attack leveraging the relevant issues on the attestation enclave, and
+    /// whether the attesting enclave employs adequate software hardening to
+    /// mitigate the risk.
+    ConfigurationAndSwHardeningNeeded,
+    /// DCAP specific quote status. The Quote is good but TCB level of the
+    /// platform is out of date. The platform needs patching to be at the latest
+    /// TCB level.
+    OutOfDate,
+    /// DCAP specific quote status. The Quote is good but the TCB level of the
+    /// platform is out of date and additional configuration of the SGX Platform
+    /// at its current patching level may be needed. The platform needs patching
+    /// to be at the latest TCB level.
+    OutOfDateConfigurationNeeded,
+    /// Other unknown bad status.
+    UnknownBadStatus,
+}
+
+impl SgxQuoteStatus {
+    /// The quote is not trustworthy and no amount of retrying will change that,
+    /// e.g. because the platform key or EPID group was revoked
+    pub fn is_fatal(&self) -> bool {
+        matches!(
+            self,
+            SgxQuoteStatus::SignatureInvalid
+                | SgxQuoteStatus::GroupRevoked
+                | SgxQuoteStatus::SignatureRevoked
+                | SgxQuoteStatus::KeyRevoked
+        )
+    }
+
+    /// The attestation may succeed if redone, e.g. after the client refreshed
+    /// its SigRL from IAS
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, SgxQuoteStatus::SigrlVersionMismatch)
+    }
+}
+
+impl From<&SgxQuoteStatus> for NodeAuthResult {
+    fn from(status: &SgxQuoteStatus) -> Self {
+        match status {
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded => {
+                NodeAuthResult::SwHardeningAndConfigurationNeeded
+            }
+            SgxQuoteStatus::ConfigurationNeeded => NodeAuthResult::ConfigurationNeeded,
+            SgxQuoteStatus::GroupOutOfDate => NodeAuthResult::GroupOutOfDate,
+            SgxQuoteStatus::KeyRevoked => NodeAuthResult::KeyRevoked,
+            SgxQuoteStatus::SigrlVersionMismatch => NodeAuthResult::SigrlVersionMismatch,
+            SgxQuoteStatus::SignatureRevoked => NodeAuthResult::SignatureRevoked,
+            SgxQuoteStatus::GroupRevoked => NodeAuthResult::GroupRevoked,
+            _ => NodeAuthResult::BadQuoteStatus,
+        }
+    }
+}
+
+impl SgxQuoteStatus {
+    /// Name of the status in attestation reports, e.g. `GROUP_OUT_OF_DATE`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SgxQuoteStatus::OK => "OK",
+            SgxQuoteStatus::SignatureInvalid => "SIGNATURE_INVALID",
+            SgxQuoteStatus::GroupRevoked => "GROUP_REVOKED",
+            SgxQuoteStatus::SignatureRevoked => "SIGNATURE_REVOKED",
+            SgxQuoteStatus::KeyRevoked => "KEY_REVOKED",
+            SgxQuoteStatus::SigrlVersionMismatch => "SIGRL_VERSION_MISMATCH",
+            SgxQuoteStatus::GroupOutOfDate => "GROUP_OUT_OF_DATE",
+            SgxQuoteStatus::OutOfDate => "OUT_OF_DATE",
+            SgxQuoteStatus::OutOfDateConfigurationNeeded => "OUT_OF_DATE_CONFIGURATION_NEEDED",
+            SgxQuoteStatus::ConfigurationNeeded => "CONFIGURATION_NEEDED",
+            SgxQuoteStatus::SwHardeningNeeded => "SW_HARDENING_NEEDED",
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded => {
+                "CONFIGURATION_AND_SW_HARDENING_NEEDED"
+            }
+            SgxQuoteStatus::UnknownBadStatus => "UNKNOWN_BAD_STATUS",
+        }
+    }
+}
+
+impl From<&str> for SgxQuoteStatus {
+    /// Convert from str status from the report to enum.
+    fn from(status: &str) -> Self {
+        match status {
+            "OK" => SgxQuoteStatus::OK,
+            "SIGNATURE_INVALID" => SgxQuoteStatus::SignatureInvalid,
+            "GROUP_REVOKED" => SgxQuoteStatus::GroupRevoked,
+            "SIGNATURE_REVOKED" => SgxQuoteStatus::SignatureRevoked,
+            "KEY_REVOKED" => SgxQuoteStatus::KeyRevoked,
+            "SIGRL_VERSION_MISMATCH" => SgxQuoteStatus::SigrlVersionMismatch,
+            "GROUP_OUT_OF_DATE" => SgxQuoteStatus::GroupOutOfDate,
+            "OUT_OF_DATE" => SgxQuoteStatus::OutOfDate,
+            "OUT_OF_DATE_CONFIGURATION_NEEDED" => SgxQuoteStatus::OutOfDateConfigurationNeeded,
+            "CONFIGURATION_NEEDED" => SgxQuoteStatus::ConfigurationNeeded,
+            "SW_HARDENING_NEEDED" => SgxQuoteStatus::SwHardeningNeeded,
+            "CONFIGURATION_AND_SW_HARDENING_NEEDED" => {
+                SgxQuoteStatus::ConfigurationAndSwHardeningNeeded
+            }
+            _ => SgxQuoteStatus::UnknownBadStatus,
+        }
+    }
+}
+
+/// An application that hosts an enclave can ask the enclave to produce a report
+/// (`SgxEnclaveReport`) and then pass this report to a platform service
+/// (Quoting Enclave) to produce a type of credential that reflects the enclave
+/// and platform state. The quote can be passed to entities off the platform,
+/// and verified using Intel EPID signature verification techniques.
+#[derive(PartialEq)]
+pub struct SgxQuote {
+    /// Version of the quote structure
+    pub version: SgxQuoteVersion,
+    /// ID of the Intel EPID group of the platform belongs to
+    pub gid: EpidGroupId,
+    /// Security version number of Quoting Enclave
+    pub isv_svn_qe: u16,
+    /// Security version number of PCE
+    pub isv_svn_pce: u16,
+    /// Vendor ID of Quoting Enclave
+    pub qe_vendor_id: Uuid,
+    /// User data
+    pub user_data: [u8; 20],
+    /// Report generated by the enclave
+    pub isv_enclave_report: SgxEnclaveReport,
+    /// Report of the Quoting Enclave, found in the signature data of ECDSA
+    /// (V3) quotes
+    qe_report: Option<SgxEnclaveReport>,
+    /// Location of the signature data in the raw quote, for ECDSA (V3) quotes
+    signature_data: Option<std::ops::Range<usize>>,
+    /// Type and content of the certification data at the end of the signature
+    /// data of ECDSA (V3) quotes, e.g. the PCK certificate chain
+    certification_data: Option<(u16, Vec<u8>)>,
+}
+
+impl std::fmt::Debug for SgxQuote {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version: {:?}", self.version)?;
+        writeln!(f, "gid: {:?}", self.gid)?;
+        writeln!(f, "isv_svn_qe: {}", self.isv_svn_qe)?;
+        writeln!(f, "isv_svn_pce: {}", self.isv_svn_pce)?;
+        writeln!(f, "qe_vendor_id: {}", self.qe_vendor_id)?;
+        writeln!(f, "user_data: {:?}", &self.user_data)?;
+        writeln!(f, "isv_enclave_report: \n{:?}", self.isv_enclave_report)?;
+        writeln!(f, "qe_report: \n{:?}", self.qe_report)?;
+        writeln!(f, "signature_data: {:?}", self.signature_data)?;
+        writeln!(
+            f,
+            "certification_data: {:?}",
+            self.certification_data
+                .as_ref()
+                .map(|(cert_type, data)| (cert_type, data.len()))
+        )
+    }
+}
+
+/// Return the `n` bytes at `pos` and move `pos` past them, or fail if `bytes`
+/// is too short. Reading 0 bytes always succeeds.
+fn take_bytes<'a>(bytes: &'a [u8], pos: &mut usize, n: usize) -> Result<&'a [u8], Error> {
+    let end = pos.checked_add(n).unwrap_or(usize::MAX);
+    match bytes.get(*pos..end) {
+        Some(ret) => {
+            *pos = end;
+            Ok(ret)
+        }
+        None => {
+            warn!("Quote parsing error - quote too short");
+            Err(Error::ReportTruncated {
+                expected: end,
+                got: bytes.len(),
+            })
+        }
+    }
+}
+
+impl SgxQuote {
+    /// Parse from bytes to `SgxQuote`.
+    fn parse_from<'a>(bytes: &'a [u8]) -> Result<Self, Error> {
+        let mut pos: usize = 0;
+        let mut take = |n: usize| take_bytes(bytes, &mut pos, n);
+
+        // Parse by bytes according to specifications.
+        // off 0, size 2 + 2
+        let raw_version = u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?);
+        let version = match raw_version {
+            1 => {
+                let signature_type = SgxEpidQuoteSigType::from_u16(u16::from_le_bytes(
+                    <[u8; 2]>::try_from(take(2)?)?,
+                ))?;
+                SgxQuoteVersion::V1(signature_type)
+            }
+            2 => {
+                let signature_type = SgxEpidQuoteSigType::from_u16(u16::from_le_bytes(
+                    <[u8; 2]>::try_from(take(2)?)?,
+                ))?;
+                SgxQuoteVersion::V2(signature_type)
+            }
+            3 => {
+                let attestation_key_type = match u16::from_le_bytes(<[u8; 2]>::try_from(take(2)?)?)
+                {
+                    2 => SgxEcdsaQuoteAkType::P256_256,
+                    3 => SgxEcdsaQuoteAkType::P384_384,
+                    _ => {
+                        warn!("Quote parsing error - ecdsa quote type invalid");
+                        return Err(Error::ReportParseError);
+                    }
+                };
+                SgxQuoteVersion::V3(attestation_key_type)
+            }
+            _ => {
+                warn!(
+                    "Quote parsing error - Unknown quote version {}",
+                    raw_version
+                );
+                return Err(Error::UnsupportedQuoteVersion(raw_version));
+            }
+        };
+
+        // off 4, size 4
+        let gid = EpidGroupId(u32::from_le_bytes(<[u8; 4]>::try_from(take(4).map_err(
+            |e| {
+                warn!("Failed to parse quote gid");
+                e
+            },
+        )?)?));
+
+        // off 8, size 2
+        let isv_svn_qe = u16::from_le_bytes(<[u8; 2]>::try_from(take(2).map_err(|e| {
+            warn!("Failed to parse quote isv svn qe");
+            e
+        })?)?);
+
+        // off 10, size 2
+        let isv_svn_pce = u16::from_le_bytes(<[u8; 2]>::try_from(take(2).map_err(|e| {
+            warn!("Failed to parse quote isv svn");
+            e
+        })?)?);
+
+        // off 12, size 16
+        let qe_vendor_id_raw = <[u8; 16]>::try_from(take(16)?)?;
+        let qe_vendor_id = Uuid::from_slice(&qe_vendor_id_raw).map_err(|_| {
+            warn!("Failed to parse quote vendor id");
+            Error::ReportParseError
+        })?;
+
+        // off 28, size 20
+        let user_data = <[u8; 20]>::try_from(take(20).map_err(|e| {
+            warn!("Failed to parse quote user data");
+            e
+        })?)?;
+
+        // off 48, size 384
+        let isv_enclave_report =
+            SgxEnclaveReport::parse_from(take(REPORT_BODY_LEN).map_err(|e| {
+                warn!("Failed to parse enclave report");
+                e
+            })?)?;
+
+        // ECDSA quotes may carry their signature data, which holds the QE report
+        let mut qe_report = None;
+        let mut signature_data = None;
+        let mut certification_data = None;
+        if let SgxQuoteVersion::V3(_) = version {
+            if bytes.len() > 48 + REPORT_BODY_LEN {
+                // off 432, size 4
+                let sig_data_len =
+                    u32::from_le_bytes(<[u8; 4]>::try_from(take(4).map_err(|e| {
+                        warn!("Failed to parse quote signature data length");
+                        e
+                    })?)?) as usize;
+
+                // off 436, size sig_data_len. The QE report follows the ISV
+                // enclave report signature (64) and the attestation key (64)
+                if sig_data_len < ECDSA_QE_REPORT_OFFSET + REPORT_BODY_LEN {
+                    warn!(
+                        "Quote signature data too short for the QE report: {}",
+                        sig_data_len
+                    );
+                    return Err(Error::BadFieldLength {
+                        field: "signature_data",
+                    });
+                }
+                // the signature data is the tail of the quote, so its declared
+                // length must account for exactly the rest of the buffer
+                let remaining = bytes.len() - ECDSA_SIG_DATA_OFFSET;
+                if sig_data_len != remaining {
+                    warn!(
+                        "Quote signature data length is {} but {} bytes are left",
+                        sig_data_len, remaining
+                    );
+                    return Err(Error::ReportParseError);
+                }
+                let sig_data = take(sig_data_len).map_err(|e| {
+                    warn!("Failed to parse quote signature data");
+                    e
+                })?;
+                let (qe_report_body, _) =
+                    SgxEnclaveReport::parse_from_at(sig_data, ECDSA_QE_REPORT_OFFSET)?;
+                qe_report = Some(qe_report_body);
+                signature_data = Some(ECDSA_SIG_DATA_OFFSET..ECDSA_SIG_DATA_OFFSET + sig_data_len);
+                certification_data = Some(parse_certification_data(sig_data)?);
+            }
+        }
+
+        if pos != bytes.len() {
+            warn!("Quote parsing error - Quote size different from expected");
+            return Err(Error::ReportTrailingBytes(bytes.len() - pos));
+        };
+
+        Ok(Self {
+            version,
+            gid,
+            isv_svn_qe,
+            isv_svn_pce,
+            qe_vendor_id,
+            user_data,
+            isv_enclave_report,
+            qe_report,
+            signature_data,
+            certification_data,
+        })
+    }
+
+    /// Report of the Quoting Enclave, if the quote carries its signature data
+    pub fn qe_report(&self) -> Option<&SgxEnclaveReport> {
+        self.qe_report.as_ref()
+    }
+
+    /// Offset and length of the signature data in the raw quote, if the quote
+    /// carries it
+    pub fn signature_data(&self) -> Option<std::ops::Range<usize>> {
+        self.signature_data.clone()
+    }
+
+    /// Certification data of the quote signature, e.g. the PEM encoded PCK
+    /// certificate chain for type 5, if the quote carries its signature data
+    pub fn certification_data(&self) -> Option<&[u8]> {
+        self.certification_data
+            .as_ref()
+            .map(|(_, data)| data.as_slice())
+    }
+
+    /// Type of `certification_data`
+    pub fn cert_data_type(&self) -> Option<u16> {
+        self.certification_data
+            .as_ref()
+            .map(|(cert_type, _)| *cert_type)
+    }
+
+    /// Type of the key that signed the quote, for ECDSA (V3) quotes
+    pub fn att_key_type(&self) -> Option<SgxEcdsaQuoteAkType> {
+        match self.version {
+            SgxQuoteVersion::V3(ak_type) => Some(ak_type),
+            _ => None,
+        }
+    }
+
+    /// Whether the quote was produced by Intel's reference Quoting Enclave
+    pub fn is_intel_qe(&self) -> bool {
+        self.qe_vendor_id == INTEL_QE_VENDOR_ID
+    }
+
+    /// Verify that the Quoting Enclave and the PCE that produced the quote are
+    /// at least at `min_qe_svn` and `min_pce_svn`, since an outdated quoting
+    /// infrastructure is vulnerable even when the application enclave isn't
+    pub fn verify_qe_svn(&self, min_qe_svn: u16, min_pce_svn: u16) -> Result<(), NodeAuthResult> {
+        if self.isv_svn_qe < min_qe_svn {
+            warn!(
+                "isv_svn_qe of quote is too low: {} (minimum {})",
+                self.isv_svn_qe, min_qe_svn
+            );
+            return Err(NodeAuthResult::QeSvnTooLow);
+        }
+
+        if self.isv_svn_pce < min_pce_svn {
+            warn!(
+                "isv_svn_pce of quote is too low: {} (minimum {})",
+                self.isv_svn_pce, min_pce_svn
+            );
+            return Err(NodeAuthResult::PceSvnTooLow);
+        }
+
+        Ok(())
+    }
+}
+
+/// Parse the type and content of the certification data that ends the
+/// signature data of an ECDSA quote, after the QE report signature and the
+/// variable length QE authentication data
+fn parse_certification_data(sig_data: &[u8]) -> Result<(u16, Vec<u8>), Error> {
+    let mut pos = ECDSA_QE_AUTH_DATA_OFFSET;
+    let mut take = |n: usize| take_bytes(sig_data, &mut pos, n);
+    let bad_length = |_| {
+        warn!("Quote signature data too short for the certification data");
+        Error::BadFieldLength {
+            field: "certification_data",
+        }
+    };
+
+    let qe_auth_data_len = u16::from_le_bytes(<[u8; 2]>::try_from(take(2).map_err(bad_length)?)?);
+    take(qe_auth_data_len as usize).map_err(bad_length)?;
+
+    let cert_data_type = u16::from_le_bytes(<[u8; 2]>::try_from(take(2).map_err(bad_length)?)?);
+    let cert_data_len = u32::from_le_bytes(<[u8; 4]>::try_from(take(4).map_err(bad_length)?)?);
+    let cert_data = take(cert_data_len as usize).map_err(bad_length)?;
+
+    if pos != sig_data.len() {
+        warn!("Quote parsing error - bytes left after the certification data");
+        return Err(Error::ReportTrailingBytes(sig_data.len() - pos));
+    }
+
+    Ok((cert_data_type, cert_data.to_vec()))
+}
+
+/// Vendor ID of Intel's reference Quoting Enclave for ECDSA quotes
+pub const INTEL_QE_VENDOR_ID: Uuid = Uuid::from_bytes([
+    0x93, 0x9a, 0x72, 0x33, 0xf7, 0x9c, 0x4c, 0xa9, 0x94, 0x0a, 0x0d, 0xb3, 0x95, 0x7f, 0x06, 0x07,
+]);
+
+/// Offset of the signature data in an ECDSA quote, after the header, the ISV
+/// enclave report and the signature data length
+const ECDSA_SIG_DATA_OFFSET: usize = 48 + REPORT_BODY_LEN + 4;
+
+/// Offset of the QE report inside the signature data of an ECDSA quote
+const ECDSA_QE_REPORT_OFFSET: usize = 128;
+
+/// Offset of the QE authentication data size inside the signature data of an
+/// ECDSA quote, after the QE report and its signature (64)
+const ECDSA_QE_AUTH_DATA_OFFSET: usize = ECDSA_QE_REPORT_OFFSET + REPORT_BODY_LEN + 64;
+
+/// Advisories that are tolerated in every build
+const BASE_WHITELISTED_ADVISORIES: &[&str] = &[
+    "INTEL-SA-00334",
+    "INTEL-SA-00219",
+    "INTEL-SA-00615",
+    "INTEL-SA-00657",
+    "INTEL-SA-00767",
+];
+
+/// Advisories that are only tolerated in development builds. Production
+/// builds treat them as vulnerabilities
+const DEV_ONLY_ADVISORIES: &[&str] = &[];
+
+// The production whitelist is the base list alone, so it's a subset of the
+// development one. A dev-only entry that is also in the base list would be
+// whitelisted in production too.
+const _: () = assert!(
+    lists_disjoint(BASE_WHITELISTED_ADVISORIES, DEV_ONLY_ADVISORIES),
+    "DEV_ONLY_ADVISORIES must not repeat BASE_WHITELISTED_ADVISORIES"
+);
+
+const fn lists_disjoint(a: &[&str], b: &[&str]) -> bool {
+    let mut i = 0;
+    while i < a.len() {
+        let mut j = 0;
+        while j < b.len() {
+            if const_str_eq(a[i], b[j]) {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+const fn const_str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+    if a.len() != b.len() {
+        return false;
+    }
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// The advisories whitelisted in this build
+fn whitelisted_advisories() -> impl Iterator<Item = &'static str> {
+    let dev_only: &'static [&'static str] = if cfg!(feature = "production") {
+        &[]
+    } else {
+        DEV_ONLY_ADVISORIES
+    };
+    BASE_WHITELISTED_ADVISORIES
+        .iter()
+        .chain(dev_only.iter())
+        .copied()
+}
+
+lazy_static! {
+    static ref ADVISORY_DESC: HashMap<&'static str, &'static str> = [
+        (
+            "INTEL-SA-00161",
+            "You must disable hyperthreading in the BIOS"
+        ),
+        (
+            "INTEL-SA-00219",
+            "You must disable the integrated graphics in the BIOS, or update the BIOS to the latest microcode"
+        ),
+        (
+            "INTEL-SA-00233",
+            "You must update the BIOS to the latest microcode, or disable TSX in the BIOS"
+        ),
+        (
+            "INTEL-SA-00289",
+            "You must disable overclocking/undervolting in the BIOS"
+        ),
+        (
+            "INTEL-SA-00320",
+            "You must update the BIOS to the latest microcode"
+        ),
+        (
+            "INTEL-SA-00334",
+            "Mitigated by the enclave's LVI hardening, no BIOS action is needed"
+        ),
+        (
+            "INTEL-SA-00615",
+            "You must update the BIOS to the latest microcode"
+        ),
+        (
+            "INTEL-SA-00657",
+            "You must update the BIOS to the latest microcode and enable x2APIC mode in the BIOS"
+        ),
+        (
+            "INTEL-SA-00767",
+            "You must update the BIOS to the latest microcode"
+        ),
+    ]
+    .iter()
+    .copied()
+    .collect();
+    static ref ADVISORY_SEVERITY: HashMap<&'static str, AdvisorySeverity> = [
+        ("INTEL-SA-00161", AdvisorySeverity::High),
+        ("INTEL-SA-00219", AdvisorySeverity::Medium),
+        ("INTEL-SA-00289", AdvisorySeverity::High),
+        ("INTEL-SA-00334", AdvisorySeverity::Medium),
+        ("INTEL-SA-00615", AdvisorySeverity::Medium),
+        ("INTEL-SA-00657", AdvisorySeverity::Medium),
+    ]
+    .iter()
+    .copied()
+    .collect();
+}
+
+/// What an operator has to do to mitigate the advisory `id`, if known
+pub fn describe(id: &str) -> Option<&'static str> {
+    ADVISORY_DESC
+        .get(normalize_advisory_id(id).as_str())
+        .copied()
+}
+
+/// Severity of an advisory, as rated by Intel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdvisorySeverity {
+    Critical,
+    High,
+    Medium,
+    Low,
+    Unknown,
+}
+
+/// An advisory the platform is vulnerable to
+#[derive(Debug, Clone, PartialEq)]
+pub struct Advisory {
+    pub id: String,
+    /// What has to be done to mitigate it, if known
+    pub description: Option<String>,
+    pub severity: AdvisorySeverity,
+}
+
+/// Canonicalize the numeric suffix of an advisory ID to 5 digits, so that e.g.
+/// `INTEL-SA-334` and `INTEL-SA-00334` compare equal
+pub(crate) fn normalize_advisory_id(s: &str) -> String {
+    let s = s.trim();
+    match s.rfind('-') {
+        Some(pos) => match s[pos + 1..].parse::<u32>() {
+            Ok(num) => format!("{}-{:05}", &s[..pos], num),
+            Err(_) => s.to_string(),
+        },
+        None => s.to_string(),
+    }
+}
+
+/// Whether two advisory IDs are the same once normalized, without allocating
+#[cfg(feature = "SGX_MODE_HW")]
+fn advisory_ids_match(a: &str, b: &str) -> bool {
+    let (a, b) = (a.trim(), b.trim());
+    match (a.rfind('-'), b.rfind('-')) {
+        (Some(pos_a), Some(pos_b)) if a[..pos_a] == b[..pos_b] => {
+            match (a[pos_a + 1..].parse::<u32>(), b[pos_b + 1..].parse::<u32>()) {
+                (Ok(num_a), Ok(num_b)) => num_a == num_b,
+                _ => a == b,
+            }
+        }
+        _ => a == b,
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct AdvisoryIDs(pub Vec<String>);
+
+impl AdvisoryIDs {
+    /// Advisories listed in the `advisoryIDs` array of an IAS report. The
+    /// array is left out when there are none, e.g. for an `OK` quote status,
+    /// and entries that aren't strings are skipped.
+    pub fn from_ias_report(report: &Value) -> AdvisoryIDs {
+        let ids = match report.get("advisoryIDs").and_then(Value::as_array) {
+            Some(ids) => ids,
+            None => return AdvisoryIDs::default(),
+        };
+
+        AdvisoryIDs::deduplicated(ids.iter().filter_map(|id| {
+            let id = id.as_str();
+            if id.is_none() {
+                warn!("Skipping advisory id that isn't a string");
+            }
+            id.map(str::to_string)
+        }))
+    }
+
+    /// The advisories in `ids`, without the repeated ones. The first occurrence
+    /// of each is kept, in order.
+    fn deduplicated<I: IntoIterator<Item = String>>(ids: I) -> AdvisoryIDs {
+        let mut advisories = AdvisoryIDs::default();
+        for id in ids {
+            if !advisories.0.contains(&id) {
+                advisories.0.push(id);
+            }
+        }
+        advisories
+    }
+
+    /// Add the advisories of `other` that aren't already listed
+    pub fn merge(&mut self, other: &AdvisoryIDs) {
+        for id in other.0.iter() {
+            if !self.0.contains(id) {
+                self.0.push(id.clone());
+            }
+        }
+    }
+}
+
+/// Parse a comma separated list of advisory ids, as found in configuration
+impl std::str::FromStr for AdvisoryIDs {
+    type Err = std::convert::Infallible;
+
+    fn from_str(list: &str) -> Result<Self, Self::Err> {
+        Ok(AdvisoryIDs::deduplicated(
+            list.split(',')
+                .map(str::trim)
+                .filter(|id| !id.is_empty())
+                .map(str::to_string),
+        ))
+    }
+}
+
+#[cfg(feature = "SGX_MODE_HW")]
+impl AdvisoryIDs {
+    pub(crate) fn vulnerable(&self) -> Vec<String> {
+        self.vulnerable_against(&AdvisoryWhitelist::compiled())
+    }
+
+    /// Whether any advisory isn't whitelisted, for when the list itself isn't
+    /// needed. Unlike `vulnerable`, this doesn't allocate.
+    pub fn is_vulnerable(&self) -> bool {
+        self.0.iter().any(|id| {
+            !whitelisted_advisories().any(|whitelisted| advisory_ids_match(id, whitelisted))
+        })
+    }
+}
+
+impl AdvisoryIDs {
+    /// Advisories that aren't tolerated by `whitelist`, each followed by its
+    /// description when there is one
+    pub fn vulnerable_against(&self, whitelist: &AdvisoryWhitelist) -> Vec<String> {
+        let mut vulnerable: Vec<String> = vec![];
+        for advisory in self.vulnerable_detailed_against(whitelist) {
+            vulnerable.push(advisory.id);
+            if let Some(description) = advisory.description {
+                vulnerable.push(description)
+            }
+        }
+        vulnerable
+    }
+
+    /// Same as `vulnerable`, with the description and severity of each advisory
+    pub fn vulnerable_detailed(&self) -> Vec<Advisory> {
+        self.vulnerable_detailed_against(&AdvisoryWhitelist::compiled())
+    }
+
+    fn vulnerable_detailed_against(&self, whitelist: &AdvisoryWhitelist) -> Vec<Advisory> {
+        // entries ending with `*` whitelist every advisory starting with them
+        let (prefixes, exact): (Vec<&String>, Vec<&String>) =
+            whitelist.0.iter().partition(|a| a.trim().ends_with('*'));
+        let exact: Vec<String> = exact.iter().map(|a| normalize_advisory_id(a)).collect();
+        let prefixes: Vec<&str> = prefixes
+            .iter()
+            .map(|a| a.trim().trim_end_matches('*'))
+            .filter(|prefix| {
+                if prefix.is_empty() {
+                    warn!("Ignoring whitelist entry that would match every advisory");
+                }
+                !prefix.is_empty()
+            })
+            .collect();
+
+        let mut vulnerable = vec![];
+        let mut listed = HashSet::new();
+        for i in self.0.iter() {
+            let id = normalize_advisory_id(i);
+            // the same advisory may be listed twice, e.g. as INTEL-SA-334 and INTEL-SA-00334
+            if !listed.insert(id.clone()) {
+                continue;
+            }
+            if !exact.contains(&id) && !prefixes.iter().any(|prefix| id.starts_with(prefix)) {
+                vulnerable.push(Advisory {
+                    id: i.clone(),
+                    description: describe(&id).map(|v| v.to_string()),
+                    severity: ADVISORY_SEVERITY
+                        .get(&id.as_str())
+                        .copied()
+                        .unwrap_or(AdvisorySeverity::Unknown),
+                });
+            }
+        }
+        vulnerable
+    }
+}
+
+/// Set of advisory IDs that are tolerated on a platform. An entry ending with
+/// `*` tolerates every advisory whose normalized ID starts with it, e.g.
+/// `INTEL-SA-006*`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AdvisoryWhitelist(pub Vec<String>);
+
+impl From<Vec<String>> for AdvisoryWhitelist {
+    fn from(advisory_ids: Vec<String>) -> Self {
+        Self(advisory_ids)
+    }
+}
+
+/// Layer of a layered whitelist, from the least to the most specific
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WhitelistLayer {
+    Base,
+    Org,
+    Local,
+}
+
+/// An advisory that a layer tried to add, although it isn't whitelisted by the
+/// layer below it
+#[derive(Debug, PartialEq)]
+pub struct WhitelistConflict {
+    pub advisory_id: String,
+    pub layer: WhitelistLayer,
+}
+
+impl AdvisoryWhitelist {
+    /// The whitelist compiled into the enclave, which is the most that any
+    /// other layer may allow
+    pub fn compiled() -> Self {
+        Self(whitelisted_advisories().map(|a| a.to_string()).collect())
+    }
+
+    pub fn contains(&self, advisory_id: &str) -> bool {
+        self.0.iter().any(|a| a == advisory_id)
+    }
+
+    /// Merge the org-wide and node-local whitelists on top of `base`. Each layer
+    /// can only narrow the one below it, so the most specific layer that is set
+    /// takes precedence, and entries it adds beyond its parent are dropped and
+    /// reported as conflicts.
+    pub fn layered(
+        base: &AdvisoryWhitelist,
+        org: Option<&AdvisoryWhitelist>,
+        local: Option<&AdvisoryWhitelist>,
+    ) -> (Self, Vec<WhitelistConflict>) {
+        let mut merged = base.clone();
+        let mut conflicts = vec![];
+
+        for (layer, list) in [(WhitelistLayer::Org, org), (WhitelistLayer::Local, local)] {
+            let list = match list {
+                Some(list) => list,
+                None => continue,
+            };
+
+            let mut narrowed = AdvisoryWhitelist::default();
+            for advisory_id in list.0.iter() {
+                if merged.contains(advisory_id) {
+                    if !narrowed.contains(advisory_id) {
+                        narrowed.0.push(advisory_id.clone());
+                    }
+                } else {
+                    warn!(
+                        "Advisory {} whitelisted by {:?} layer is not allowed by the layer below",
+                        advisory_id, layer
+                    );
+                    conflicts.push(WhitelistConflict {
+                        advisory_id: advisory_id.clone(),
+                        layer,
+                    });
+                }
+            }
+            merged = narrowed;
+        }
+
+        (merged, conflicts)
+    }
+}
+
+/// Rollup of the TCB status of many nodes, for monitoring
+#[derive(Debug, Default)]
+pub struct FleetTcbSummary {
+    /// Number of attestations summarized
+    pub total: usize,
+    /// Number of attestations per quote status
+    pub status_counts: HashMap<SgxQuoteStatus, usize>,
+    /// Union of the advisories reported for all the nodes
+    pub advisory_ids: AdvisoryIDs,
+}
+
+impl FleetTcbSummary {
+    pub fn from_attestations<'a, I>(attestations: I) -> Self
+    where
+        I: IntoIterator<Item = &'a AttestationReport>,
+    {
+        let mut summary = Self::default();
+        for attestation in attestations {
+            summary.total += 1;
+            *summary
+                .status_counts
+                .entry(attestation.sgx_quote_status)
+                .or_insert(0) += 1;
+            summary.advisory_ids.merge(&attestation.advisory_ids);
+        }
+
+        summary
+    }
+
+    pub fn count(&self, status: SgxQuoteStatus) -> usize {
+        self.status_counts.get(&status).copied().unwrap_or(0)
+    }
+}
+
+/// A report that can be signed by Intel EPID (which generates
+/// `EndorsedAttestationReport`) and then sent off of the platform to be
+/// verified by remote client.
+#[derive(Debug)]
+pub struct AttestationReport {
+    /// The freshness of the report, i.e., elapsed time after acquiring the
+    /// report in seconds.
+    pub timestamp: u64,
+    /// Quote status
+    pub sgx_quote_status: SgxQuoteStatus,
+    /// Content of the quote
+    pub sgx_quote_body: SgxQuote,
+    pub platform_info_blob: Option<Vec<u8>>,
+    pub advisory_ids: AdvisoryIDs,
+    pub tcb_eval_data_number: u16,
+    /// The decoded `isvEnclaveQuoteBody` that `sgx_quote_body` was parsed from
+    sgx_quote_raw: Vec<u8>,
+}
+
+/// Decode the base64 `isvEnclaveQuoteBody` of an IAS report. The body isn't
+/// always padded, so fall back to the no-padding config if the standard one fails
+fn decode_quote_body(encoded: &str) -> Result<Vec<u8>, Error> {
+    base64::decode_config(encoded, base64::STANDARD)
+        .or_else(|_| base64::decode_config(encoded, base64::STANDARD_NO_PAD))
+        .map_err(|_| {
+            warn!("Error decoding encoded quote body");
+            Error::ReportParseError
+        })
+}
+
+impl AttestationReport {
+    /// Construct a AttestationReport from a X509 certificate and verify
+    /// attestation report with the report_ca_cert which is from the attestation
+    /// service provider.
+    // just unused in SW mode
+    #[allow(dead_code)]
+    pub fn from_cert(cert: &[u8]) -> Result<Self, Error> {
+        let attn_report = Self::signed_report_json(cert)?;
+        Self::from_report_json(&attn_report)
+    }
+
+    /// The JSON document of the attestation report in `cert`, once its
+    /// signature by IAS is verified
+    fn signed_report_json(cert: &[u8]) -> Result<Value, Error> {
+        let payload = get_netscape_comment(cert).map_err(|_err| {
+            error!("Failed to get netscape comment");
+            Error::ReportParseError
+        })?;
+
+        // Convert to endorsed report
+        let report: EndorsedAttestationReport = serde_json::from_slice(&payload)?;
+
+        // Verify report's signature - aka intel's signing cert
+        let signing_cert = webpki::EndEntityCert::from(&report.signing_cert).map_err(|_err| {
+            error!("Failed to validate signature");
+            Error::ReportParseError
+        })?;
+
+        let (ias_cert, root_store) = get_ias_auth_config();
+
+        let trust_anchors: Vec<webpki::TrustAnchor> = root_store
+            .roots
+            .iter()
+            .map(|cert| cert.to_trust_anchor())
+            .collect();
+
+        let chain: Vec<&[u8]> = vec![&ias_cert];
+
+        // set as 04.11.23(dd.mm.yy) - should be valid for the foreseeable future, and not rely on SystemTime
+        let time_stamp = webpki::Time::from_seconds_since_unix_epoch(1723218496);
+
+        // note: there's no way to not validate the time, and we don't want to write this code
+        // ourselves. We also can't just ignore the error message, since that means that the rest of
+        // the validation didn't happen (time is validated early on)
+        match signing_cert.verify_is_valid_tls_server_cert(
+            SUPPORTED_SIG_ALGS,
+            &webpki::TLSServerTrustAnchors(&trust_anchors),
+            &chain,
+            time_stamp,
+        ) {
+            Ok(_) => info!("Certificate verified successfully"),
+            Err(e) => {
+                error!("Certificate verification error {:?}", e);
+                return Err(Error::ReportValidationError);
+            }
+        };
+
+        // Verify the signature against the signing cert
+        match signing_cert.verify_signature(
+            &webpki::RSA_PKCS1_2048_8192_SHA256,
+            &report.report,
+            &report.signature,
+        ) {
+            Ok(_) => info!("Signature verified successfully"),
+            Err(e) => {
+                warn!("Signature verification error {:?}", e);
+                return Err(Error::ReportParseError);
+            }
+        }
+
+        // Verify and extract information from attestation report
+        let attn_report: Value = serde_json::from_slice(&report.report)?;
+        trace!("attn_report: {}", attn_report);
+
+        Ok(attn_report)
+    }
+
+    /// Same as `from_cert`, and also reject reports issued more than `max_age`
+    /// seconds before `now`, given in seconds since the epoch
+    pub fn from_cert_with_max_age(cert: &[u8], now: u64, max_age: u64) -> Result<Self, Error> {
+        let attn_report = Self::signed_report_json(cert)?;
+        Self::from_report_json_with_max_age(&attn_report, now, max_age)
+    }
+
+    fn from_report_json_with_max_age(
+        attn_report: &Value,
+        now: u64,
+        max_age: u64,
+    ) -> Result<Self, Error> {
+        let report = Self::from_report_json(attn_report)?;
+        report.check_age(now, max_age)?;
+        Ok(report)
+    }
+
+    fn check_age(&self, now: u64, max_age: u64) -> Result<(), Error> {
+        if now.saturating_sub(self.timestamp) > max_age {
+            warn!(
+                "Attestation report from {} is more than {} seconds old",
+                self.timestamp, max_age
+            );
+            return Err(Error::AttestationExpired);
+        }
+
+        Ok(())
+    }
+
+    /// Extract the attestation report from the JSON document signed by IAS.
+    /// The quote status and the advisories are read independently, so the
+    /// advisories are kept whatever the status is.
+    fn from_report_json(attn_report: &Value) -> Result<Self, Error> {
+        // Verify API version is supported
+        let version = attn_report["version"]
+            .as_u64()
+            .ok_or(Error::ReportParseError)?;
+
+        // v5 has the same schema as v4, and adds tcbEvaluationDataNumber
+        if version != 4 && version != 5 {
+            warn!("API version incompatible");
+            return Err(Error::ReportParseError);
+        };
+
+        let mut platform_info_blob = None;
+        if let Some(blob) = attn_report["platformInfoBlob"].as_str() {
+            let as_binary = hex::decode(blob).map_err(|_| {
+                warn!("Error parsing platform info");
+                Error::ReportParseError
+            })?;
+            platform_info_blob = Some(as_binary)
+        }
+
+        // Get quote status
+        let sgx_quote_status = {
+            let status_string = attn_report["isvEnclaveQuoteStatus"]
+                .as_str()
+                .ok_or_else(|| {
+                    warn!("Error parsing enclave quote status");
                     Error::ReportParseError
                 })?;
             SgxQuoteStatus::from(status_string)
         };
 
-        // Get quote body
-        let sgx_quote_body = {
-            let quote_encoded = attn_report["isvEnclaveQuoteBody"].as_str().ok_or_else(|| {
-                warn!("Error unpacking enclave quote body");
-                Error::ReportParseError
-            })?;
-            let quote_raw = base64::decode(quote_encoded.as_bytes()).map_err(|_| {
-                warn!("Error decoding encoded quote body");
-                Error::ReportParseError
-            })?;
-            SgxQuote::parse_from(quote_raw.as_slice())?
+        // Get quote body
+        let quote_encoded = attn_report["isvEnclaveQuoteBody"].as_str().ok_or_else(|| {
+            warn!("Error unpacking enclave quote body");
+            Error::ReportParseError
+        })?;
+        let sgx_quote_raw = decode_quote_body(quote_encoded)?;
+        let sgx_quote_body = SgxQuote::parse_from(sgx_quote_raw.as_slice())?;
+
+        let advisory_ids = AdvisoryIDs::from_ias_report(attn_report);
+
+        // v4 reports predate TCB evaluation numbers, so they count as older
+        // than any TCB recovery
+        let tcb_eval_data_number = match attn_report.get("tcbEvaluationDataNumber") {
+            Some(number) => number.as_u64().ok_or(Error::ReportParseError)? as u16,
+            None if version == 4 => 0,
+            None => {
+                warn!("Missing TCB evaluation data number");
+                return Err(Error::ReportParseError);
+            }
+        };
+
+        let timestamp_str = attn_report["timestamp"]
+            .as_str()
+            .ok_or(Error::ReportParseError)?;
+
+        let timestamp_rfc = format!("{}Z", timestamp_str);
+        let time = chrono::DateTime::parse_from_rfc3339(&timestamp_rfc).map_err(|e| {
+            warn!("Failed to decode timestamp: {}", e);
+            Error::ReportParseError
+        })?;
+        let timestamp_since_epoch = time.timestamp();
+
+        // We don't actually validate the public key, since we use ephemeral certificates,
+        // and all we really care about that the report is valid and the key that is saved in the
+        // report_data field
+
+        Ok(Self {
+            timestamp: timestamp_since_epoch as u64,
+            sgx_quote_status,
+            sgx_quote_body,
+            platform_info_blob,
+            advisory_ids,
+            tcb_eval_data_number,
+            sgx_quote_raw,
+        })
+    }
+
+    /// The quote as decoded from `isvEnclaveQuoteBody`
+    pub fn quote(&self) -> &SgxQuote {
+        &self.sgx_quote_body
+    }
+
+    /// The bytes of `isvEnclaveQuoteBody` that the quote was parsed from
+    pub fn raw_quote_body(&self) -> &[u8] {
+        &self.sgx_quote_raw
+    }
+
+    /// Advisories that IAS listed for the platform along with the quote status
+    pub fn advisories(&self) -> &AdvisoryIDs {
+        &self.advisory_ids
+    }
+
+    /// One line summary of the quote status and the advisories of the platform,
+    /// with what to do about each of them when known, for operators
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn summary(&self) -> String {
+        let status = self.sgx_quote_status.as_str();
+        if self.advisory_ids.0.is_empty() {
+            return status.to_string();
+        }
+
+        let advisories: Vec<String> = self
+            .advisory_ids
+            .0
+            .iter()
+            .map(|id| match describe(id) {
+                Some(description) => format!("{} - {}", id, description),
+                None => id.clone(),
+            })
+            .collect();
+
+        format!("{} (advisories: {})", status, advisories.join(", "))
+    }
+
+    /// Decide whether the platform is acceptable from its quote status and
+    /// advisories. `OK` is accepted, while `SW_HARDENING_NEEDED` is only
+    /// accepted if every reported advisory is in `sw_hardening`, e.g. the ones
+    /// mitigated in our build, and `GROUP_OUT_OF_DATE` and
+    /// `CONFIGURATION_NEEDED` only if every reported advisory is in
+    /// `out_of_date`. Any other status is rejected.
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn check_acceptable(
+        &self,
+        sw_hardening: &AdvisoryWhitelist,
+        out_of_date: &AdvisoryWhitelist,
+    ) -> Result<(), NodeAuthResult> {
+        let whitelist = match self.sgx_quote_status {
+            SgxQuoteStatus::OK => return Ok(()),
+            SgxQuoteStatus::SwHardeningNeeded => sw_hardening,
+            SgxQuoteStatus::GroupOutOfDate | SgxQuoteStatus::ConfigurationNeeded => out_of_date,
+            _ => {
+                warn!(
+                    "Quote status is not acceptable: {:?}",
+                    self.sgx_quote_status
+                );
+                return Err(NodeAuthResult::from(&self.sgx_quote_status));
+            }
+        };
+
+        let vulnerable = self.advisory_ids.vulnerable_against(whitelist);
+        if !vulnerable.is_empty() {
+            warn!(
+                "Quote status {:?} with advisories that aren't whitelisted: {:?}",
+                self.sgx_quote_status, vulnerable
+            );
+            return Err(NodeAuthResult::from(&self.sgx_quote_status));
+        }
+
+        Ok(())
+    }
+
+    /// Verify the attestation of a node end to end, dispatching on whether it
+    /// is endorsed by IAS or by DCAP. An IAS certificate has its report
+    /// extracted and checked against Intel's signature, then its quote status,
+    /// advisories and freshness are checked. A DCAP quote is verified against
+    /// its collateral at the time of `clock`, which covers the TCB status and
+    /// the expiry of the collateral. Both then have the enclave measurement and
+    /// the key bound into the report checked. Fails with the first stage that
+    /// didn't pass.
+    ///
+    /// Reading the (untrusted) time of `clock` and verifying a DCAP quote leave
+    /// the enclave, so they are subject to the deadline of `policy`: they aren't
+    /// run once the deadline passed, and their result is discarded if they ran
+    /// past it. The stage they belong to then fails with `DeadlineExceeded`.
+    pub fn verify_full(
+        evidence: &AttestationEvidence,
+        policy: &VerifyPolicy,
+        clock: &dyn Clock,
+    ) -> Result<VerifiedNode, Error> {
+        let report = match *evidence {
+            AttestationEvidence::Ias(cert_der) => Self::verify_ias(cert_der, policy, clock)?,
+            AttestationEvidence::Dcap { quote, collateral } => {
+                Self::verify_dcap(quote, collateral, policy, clock)?
+            }
+        };
+
+        let enclave_report = &report.sgx_quote_body.isv_enclave_report;
+        enclave_report
+            .verify_with_policy(policy)
+            .map_err(|e| verify_failed(VerifyStage::Measurement, e))?;
+
+        let public_key = enclave_report
+            .get_owner_key()
+            .map_err(|_| verify_failed(VerifyStage::Binding, NodeAuthResult::MalformedPublicKey))?;
+
+        Ok(VerifiedNode {
+            public_key,
+            attestation: report,
+        })
+    }
+
+    fn verify_ias(
+        cert_der: &[u8],
+        policy: &VerifyPolicy,
+        clock: &dyn Clock,
+    ) -> Result<AttestationReport, Error> {
+        let report = AttestationReport::from_cert(cert_der)
+            .map_err(|_| verify_failed(VerifyStage::Parse, NodeAuthResult::InvalidCert))?;
+
+        #[cfg(feature = "SGX_MODE_HW")]
+        verify_quote_status(&report, &report.advisory_ids)
+            .map_err(|e| verify_failed(VerifyStage::QuoteStatus, e))?;
+        #[cfg(not(feature = "SGX_MODE_HW"))]
+        if report.sgx_quote_status.is_fatal() {
+            return Err(verify_failed(
+                VerifyStage::QuoteStatus,
+                NodeAuthResult::from(&report.sgx_quote_status),
+            ));
+        }
+
+        if let Some(max_age) = policy.max_report_age {
+            let now = within_deadline(policy.deadline, VerifyStage::Freshness, || Ok(clock.now()))?;
+            report
+                .check_age(now, max_age)
+                .map_err(|_| verify_failed(VerifyStage::Freshness, NodeAuthResult::InvalidCert))?;
+        }
+
+        Ok(report)
+    }
+
+    fn verify_dcap(
+        quote: &[u8],
+        collateral: &[u8],
+        policy: &VerifyPolicy,
+        clock: &dyn Clock,
+    ) -> Result<AttestationReport, Error> {
+        let sgx_quote_body = SgxQuote::parse_from(quote)
+            .map_err(|_| verify_failed(VerifyStage::Parse, NodeAuthResult::InvalidCert))?;
+
+        let (now, qv_result) = within_deadline(policy.deadline, VerifyStage::Signature, || {
+            let now = clock.now();
+            let (_, qv_result) = verify_quote_ecdsa(quote, collateral, now as i64)
+                .map_err(|_| verify_failed(VerifyStage::Signature, NodeAuthResult::InvalidCert))?;
+            Ok((now, qv_result))
+        })?;
+
+        // verify_quote_ecdsa only accepts these two results
+        let sgx_quote_status =
+            if qv_result == sgx_ql_qv_result_t::SGX_QL_QV_RESULT_SW_HARDENING_NEEDED {
+                SgxQuoteStatus::SwHardeningNeeded
+            } else {
+                SgxQuoteStatus::OK
+            };
+
+        Ok(AttestationReport {
+            timestamp: now,
+            sgx_quote_status,
+            sgx_quote_body,
+            platform_info_blob: None,
+            advisory_ids: AdvisoryIDs::default(),
+            tcb_eval_data_number: 0,
+            sgx_quote_raw: quote.to_vec(),
+        })
+    }
+}
+
+fn verify_failed(stage: VerifyStage, reason: NodeAuthResult) -> Error {
+    warn!("Attestation verification failed at {:?}: {}", stage, reason);
+    Error::VerifyFailed { stage, reason }
+}
+
+/// Run `step` of `stage` unless `deadline` already passed, and discard its
+/// result if it ran past `deadline`
+fn within_deadline<T, F>(deadline: Option<Instant>, stage: VerifyStage, step: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    check_deadline(deadline).map_err(|e| verify_failed(stage, e))?;
+    let result = step();
+    check_deadline(deadline).map_err(|e| verify_failed(stage, e))?;
+
+    result
+}
+
+/// Attestation of a node, in either of the forms `AttestationReport::verify_full`
+/// accepts
+pub enum AttestationEvidence<'a> {
+    /// Certificate carrying a report signed by the Intel Attestation Service
+    Ias(&'a [u8]),
+    /// ECDSA quote and the collateral to verify it with
+    Dcap {
+        quote: &'a [u8],
+        collateral: &'a [u8],
+    },
+}
+
+/// Source of the current time, in seconds since the unix epoch
+pub trait Clock {
+    fn now(&self) -> u64;
+}
+
+/// Clock backed by the (untrusted) system time
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> u64 {
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+/// A node whose attestation passed `AttestationReport::verify_full`
+#[derive(Debug)]
+pub struct VerifiedNode {
+    /// Public key bound into the report of the node
+    pub public_key: NodeAuthPublicKey,
+    /// For DCAP, this is built from the quote: it has no advisories, and its
+    /// timestamp is the time it was verified at
+    pub attestation: AttestationReport,
+}
+
+#[cfg(feature = "test")]
+pub mod tests {
+    use serde_json::json;
+    use std::io::Read;
+    use std::untrusted::fs::File;
+
+    #[cfg(all(feature = "mock", not(feature = "SGX_MODE_HW")))]
+    use sgx_types::sgx_report_t;
+
+    use super::*;
+
+    fn tls_ra_cert_der_test() -> Vec<u8> {
+        let mut cert = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/attestation_cert_hw_invalid_test.der")
+                .unwrap();
+        f.read_to_end(&mut cert).unwrap();
+
+        cert
+    }
+
+    fn tls_ra_cert_der_v3() -> Vec<u8> {
+        let mut cert = vec![];
+        let mut f = File::open("../execute/src/registration/fixtures/tls_ra_cert_v3.der").unwrap();
+        f.read_to_end(&mut cert).unwrap();
+
+        cert
+    }
+
+    fn tls_ra_cert_der_v4() -> Vec<u8> {
+        let mut cert = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/attestation_cert_out_of_date.der")
+                .unwrap();
+        f.read_to_end(&mut cert).unwrap();
+
+        cert
+    }
+
+    fn _test_aes_encrypttls_ra_cert_der_out_of_date() -> Vec<u8> {
+        let mut cert = vec![];
+        let mut f = File::open(
+            "../execute/src/registration/fixtures/attestation_cert_sw_config_needed.der",
+        )
+        .unwrap();
+        f.read_to_end(&mut cert).unwrap();
+
+        cert
+    }
+
+    fn _ias_root_ca_cert_der() -> Vec<u8> {
+        let mut cert = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/ias_root_ca_cert.der").unwrap();
+        f.read_to_end(&mut cert).unwrap();
+
+        cert
+    }
+
+    fn attesation_report() -> Value {
+        let report = json!({
+            "version": 3,
+            "timestamp": "2020-02-11T22:25:59.682915",
+            "platformInfoBlob": "1502006504000900000D0D02040180030000000000000000000\
+                                 A00000B000000020000000000000B2FE0AE0F7FD4D552BF7EF4\
+                                 C938D44E349F1BD0E76F041362DC52B43B7B25994978D792137\
+                                 90362F6DAE91797ACF5BD5072E45F9A60795D1FFB10140421D8\
+                                 691FFD",
+            "isvEnclaveQuoteStatus": "GROUP_OUT_OF_DATE",
+            "isvEnclaveQuoteBody": "AgABAC8LAAAKAAkAAAAAAK1zRQOIpndiP4IhlnW2AkwAAAAA\
+                                    AAAAAAAAAAAAAAAABQ4CBf+AAAAAAAAAAAAAAAAAAAAAAAAA\
+                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABwAAAAAAAAAHAAAA\
+                                    AAAAADMKqRCjd2eA4gAmrj2sB68OWpMfhPH4MH27hZAvWGlT\
+                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACD1xnn\
+                                    ferKFHD2uvYqTXdDA8iZ22kCD5xw7h38CMfOngAAAAAAAAAA\
+                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
+                                    AAAAAAAAAADYIY9k0MVmCdIDUuFLf/2bGIHAfPjO9nvC7fgz\
+                                    rQedeA3WW4dFeI6oe+RCLdV3XYD1n6lEZjITOzPPLWDxulGz",
+            "id": "53530608302195762335736519878284384788",
+            "epidPseudonym": "NRksaQej8R/SyyHpZXzQGNBXqfrzPy5KCxcmJrEjupXrq3xrm2y2+J\
+                              p0IBVtcW15MCekYs9K3UH82fPyj6F5ciJoMsgEMEIvRR+csX9uyd54\
+                              p+m+/RVyuGYhWbhUcpJigdI5Q3x04GG/A7EP10j/zypwqhYLQh0qN1\
+                              ykYt1N1P0="
+        });
+
+        report
+    }
+
+    pub fn test_sgx_quote_parse_from() {
+        let attn_report = attesation_report();
+        let sgx_quote_body_encoded = attn_report["isvEnclaveQuoteBody"].as_str().unwrap();
+        let quote_raw = base64::decode(&sgx_quote_body_encoded.as_bytes()).unwrap();
+        let sgx_quote = SgxQuote::parse_from(quote_raw.as_slice()).unwrap();
+
+        assert_eq!(
+            sgx_quote.version,
+            SgxQuoteVersion::V2(SgxEpidQuoteSigType::Linkable)
+        );
+        assert_eq!(sgx_quote.gid, EpidGroupId(2863));
+        assert_eq!(sgx_quote.isv_svn_qe, 10);
+        assert_eq!(sgx_quote.isv_svn_pce, 9);
+        assert_eq!(
+            sgx_quote.qe_vendor_id,
+            Uuid::parse_str("00000000-ad73-4503-88a6-77623f822196").unwrap()
+        );
+        assert_eq!(
+            sgx_quote.user_data,
+            [117, 182, 2, 76, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+
+        let isv_enclave_report = sgx_quote.isv_enclave_report;
+        assert_eq!(
+            isv_enclave_report.cpu_svn,
+            [5, 14, 2, 5, 255, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(isv_enclave_report.misc_select, 0);
+        assert_eq!(
+            isv_enclave_report.attributes,
+            SgxReportAttributes { flags: 7, xfrm: 7 }
+        );
+        assert_eq!(
+            isv_enclave_report.mr_enclave,
+            [
+                51, 10, 169, 16, 163, 119, 103, 128, 226, 0, 38, 174, 61, 172, 7, 175, 14, 90, 147,
+                31, 132, 241, 248, 48, 125, 187, 133, 144, 47, 88, 105, 83
+            ]
+        );
+        assert_eq!(
+            isv_enclave_report.mr_signer,
+            [
+                131, 215, 25, 231, 125, 234, 202, 20, 112, 246, 186, 246, 42, 77, 119, 67, 3, 200,
+                153, 219, 105, 2, 15, 156, 112, 238, 29, 252, 8, 199, 206, 158
+            ]
+        );
+        assert_eq!(isv_enclave_report.isv_prod_id, 0);
+        assert_eq!(isv_enclave_report.isv_svn, 0);
+        assert_eq!(
+            isv_enclave_report.report_data.to_vec(),
+            [
+                216, 33, 143, 100, 208, 197, 102, 9, 210, 3, 82, 225, 75, 127, 253, 155, 24, 129,
+                192, 124, 248, 206, 246, 123, 194, 237, 248, 51, 173, 7, 157, 120, 13, 214, 91,
+                135, 69, 120, 142, 168, 123, 228, 66, 45, 213, 119, 93, 128, 245, 159, 169, 68,
+                102, 50, 19, 59, 51, 207, 45, 96, 241, 186, 81, 179
+            ]
+            .to_vec()
+        );
+    }
+
+    fn enclave_report_bytes() -> Vec<u8> {
+        let attn_report = attesation_report();
+        let sgx_quote_body_encoded = attn_report["isvEnclaveQuoteBody"].as_str().unwrap();
+        let quote_raw = base64::decode(&sgx_quote_body_encoded.as_bytes()).unwrap();
+
+        quote_raw[48..432].to_vec()
+    }
+
+    fn sgx_report_bytes(key_id: &[u8; 32]) -> Vec<u8> {
+        let mut report = enclave_report_bytes();
+        report.extend_from_slice(key_id);
+        report.extend_from_slice(&[0xAB; 16]);
+
+        report
+    }
+
+    pub fn test_sgx_report_key_id_match() {
+        let key_id = [7u8; 32];
+        let report = SgxReport::parse_from(&sgx_report_bytes(&key_id)).unwrap();
+
+        assert_eq!(report.key_id, key_id);
+        assert_eq!(report.mac, [0xAB; 16]);
+        assert_eq!(report.body.isv_svn, 0);
+        assert!(report.verify_key_id(&key_id).is_ok());
+    }
+
+    pub fn test_sgx_report_key_id_mismatch() {
+        let report = SgxReport::parse_from(&sgx_report_bytes(&[7u8; 32])).unwrap();
+
+        assert_eq!(
+            report.verify_key_id(&[8u8; 32]),
+            Err(NodeAuthResult::KeyIdMismatch)
+        );
+    }
+
+    pub fn test_sgx_report_key_id_policy() {
+        let report = SgxReport {
+            body: self_enclave_report(),
+            key_id: [7u8; 32],
+            mac: [0xAB; 16],
+        };
+
+        assert_eq!(report.verify_with_policy(&VerifyPolicy::default()), Ok(()));
+        let policy = VerifyPolicy {
+            key_id: Some([7u8; 32]),
+            ..Default::default()
+        };
+        assert_eq!(report.verify_with_policy(&policy), Ok(()));
+
+        let policy = VerifyPolicy {
+            key_id: Some([8u8; 32]),
+            ..Default::default()
+        };
+        assert_eq!(
+            report.verify_with_policy(&policy),
+            Err(NodeAuthResult::KeyIdMismatch)
+        );
+
+        // the body is still verified when the key id matches
+        let mut other_enclave = SgxReport {
+            body: self_enclave_report(),
+            key_id: [7u8; 32],
+            mac: [0xAB; 16],
+        };
+        other_enclave.body.mr_enclave = [0xa5; 32];
+        let policy = VerifyPolicy {
+            key_id: Some([7u8; 32]),
+            ..Default::default()
+        };
+        assert_eq!(
+            other_enclave.verify_with_policy(&policy),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+    }
+
+    pub fn test_cpu_svn_missing_updates() {
+        let report = SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        let cpu_svn = CpuSvn::from(report.cpu_svn);
+
+        // [5, 14, 2, 5, 255, 128, 0, ...] with components 1 and 3 behind
+        let mut baseline = report.cpu_svn;
+        baseline[1] = 15;
+        baseline[3] = 6;
+        baseline[4] = 254;
+
+        assert_eq!(cpu_svn.missing_updates_vs(&CpuSvn(baseline)), vec![1, 3]);
+        assert!(cpu_svn.missing_updates_vs(&cpu_svn).is_empty());
+    }
+
+    pub fn test_verify_svn_consistency() {
+        let mut report = SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        // [5, 14, 2, 5, 255, 128, 0, ...]
+        let cpu_svn = report.cpu_svn;
+        let mut newer_cpu_svn = cpu_svn;
+        newer_cpu_svn[1] = 15;
+
+        let matrix = SvnMatrix(
+            vec![(1, CpuSvn(cpu_svn)), (5, CpuSvn(newer_cpu_svn))]
+                .into_iter()
+                .collect(),
+        );
+
+        report.isv_svn = 0;
+        assert_eq!(report.verify_svn_consistency(&matrix), Ok(()));
+        report.isv_svn = 1;
+        assert_eq!(report.verify_svn_consistency(&matrix), Ok(()));
+        report.isv_svn = 4;
+        assert_eq!(report.verify_svn_consistency(&matrix), Ok(()));
+
+        // from isv_svn 5 onwards the platform must have the newer cpu_svn
+        report.isv_svn = 5;
+        assert_eq!(
+            report.verify_svn_consistency(&matrix),
+            Err(NodeAuthResult::SvnInconsistent)
+        );
+        report.isv_svn = 7;
+        assert_eq!(
+            report.verify_svn_consistency(&matrix),
+            Err(NodeAuthResult::SvnInconsistent)
+        );
+
+        report.cpu_svn = newer_cpu_svn;
+        assert_eq!(report.verify_svn_consistency(&matrix), Ok(()));
+    }
+
+    pub fn test_enclave_report_bad_size() {
+        let report = enclave_report_bytes();
+
+        assert_eq!(
+            SgxEnclaveReport::parse_from(&report[..368]).unwrap_err(),
+            Error::ReportTruncated {
+                expected: REPORT_BODY_LEN,
+                got: 368
+            }
+        );
+    }
+
+    pub fn test_enclave_report_display() {
+        let mut report = self_enclave_report();
+        report.mr_enclave = [0xAB; 32];
+        report.isv_svn = 3;
+        report.isv_prod_id = 7;
+        report.report_data = [0xEE; 64];
+        report.report_data[..8].copy_from_slice(&[0x01; 8]);
+
+        assert_eq!(
+            report.to_string(),
+            "mr_enclave: abababababababab..., isv_svn: 3, isv_prod_id: 7"
+        );
+
+        // the tail of report_data is only printed with full-debug
+        let debug = format!("{:?}", report);
+        #[cfg(not(feature = "full-debug"))]
+        {
+            assert!(debug.contains("report_data: [1, 1, 1, 1, 1, 1, 1, 1]..."));
+            assert!(!debug.contains("238"));
+        }
+        #[cfg(feature = "full-debug")]
+        assert!(debug.contains("238"));
+    }
+
+    pub fn test_sgx_report_size() {
+        let report = sgx_report_bytes(&[7u8; 32]);
+        assert_eq!(report.len(), SGX_REPORT_LEN);
+        assert!(SgxReport::parse_from(&report).is_ok());
+
+        // the size is checked before any field is parsed
+        assert_eq!(
+            SgxReport::parse_from(&report[..100]).err(),
+            Some(Error::ReportTruncated {
+                expected: SGX_REPORT_LEN,
+                got: 100
+            })
+        );
+        assert_eq!(
+            SgxReport::parse_from(&[]).err(),
+            Some(Error::ReportTruncated {
+                expected: SGX_REPORT_LEN,
+                got: 0
+            })
+        );
+
+        let mut long_report = report.clone();
+        long_report.extend_from_slice(&[0u8; 64]);
+        assert_eq!(
+            SgxReport::parse_from(&long_report).err(),
+            Some(Error::ReportTrailingBytes(64))
+        );
+    }
+
+    pub fn test_report_parse_errors() {
+        // truncated in the middle of a field
+        let report = sgx_report_bytes(&[7u8; 32]);
+        assert_eq!(
+            SgxReport::parse_from(&report[..420]).err(),
+            Some(Error::ReportTruncated {
+                expected: 432,
+                got: 420
+            })
+        );
+
+        let report_quote = attesation_report();
+        let quote_raw =
+            base64::decode(report_quote["isvEnclaveQuoteBody"].as_str().unwrap()).unwrap();
+        assert_eq!(
+            SgxQuote::parse_from(&quote_raw[..10]).err(),
+            Some(Error::ReportTruncated {
+                expected: 12,
+                got: 10
+            })
+        );
+
+        // bytes left over after the last field
+        let mut long_report = report.clone();
+        long_report.extend_from_slice(&[0u8; 3]);
+        assert_eq!(
+            SgxReport::parse_from(&long_report).err(),
+            Some(Error::ReportTrailingBytes(3))
+        );
+
+        let mut long_quote = quote_raw.clone();
+        long_quote.extend_from_slice(&[0u8; 2]);
+        assert_eq!(
+            SgxQuote::parse_from(&long_quote).err(),
+            Some(Error::ReportTrailingBytes(2))
+        );
+
+        // signature data too short to hold the QE report
+        let mut vec_quote = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/attestation_dcap.quote").unwrap();
+        f.read_to_end(&mut vec_quote).unwrap();
+        vec_quote[432..436].copy_from_slice(&100u32.to_le_bytes());
+        vec_quote.truncate(436 + 100);
+        assert_eq!(
+            SgxQuote::parse_from(&vec_quote).err(),
+            Some(Error::BadFieldLength {
+                field: "signature_data"
+            })
+        );
+    }
+
+    fn self_enclave_report() -> SgxEnclaveReport {
+        let body = get_cached_report().unwrap().body;
+
+        SgxEnclaveReport {
+            cpu_svn: body.cpu_svn.svn,
+            misc_select: body.misc_select,
+            isv_ext_prod_id: body.isv_ext_prod_id,
+            attributes: SgxReportAttributes {
+                flags: body.attributes.flags,
+                xfrm: body.attributes.xfrm,
+            },
+            mr_enclave: body.mr_enclave.m,
+            mr_signer: body.mr_signer.m,
+            isv_prod_id: body.isv_prod_id,
+            isv_svn: body.isv_svn,
+            config_id: body.config_id,
+            config_svn: body.config_svn,
+            isv_family_id: body.isv_family_id,
+            report_data: body.report_data.d,
+            extra: vec![],
+        }
+    }
+
+    pub fn test_enclave_report_extension() {
+        let mut report = enclave_report_bytes();
+        let expected = SgxEnclaveReport::parse_from(&report).unwrap();
+        assert!(expected.extra.is_empty());
+
+        report.extend_from_slice(&[0xAB; 16]);
+        let extended = SgxEnclaveReport::parse_from(&report).unwrap();
+        assert_eq!(extended.extra, vec![0xAB; 16]);
+        assert_eq!(extended.mr_enclave, expected.mr_enclave);
+        assert_eq!(extended.mr_signer, expected.mr_signer);
+        assert_eq!(extended.attributes, expected.attributes);
+        assert_eq!(extended.isv_svn, expected.isv_svn);
+        assert_eq!(extended.report_data[..], expected.report_data[..]);
+    }
+
+    pub fn test_enclave_report_kss() {
+        let mut report = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/enclave_report_kss.bin").unwrap();
+        f.read_to_end(&mut report).unwrap();
+
+        let report = SgxEnclaveReport::parse_from(&report).unwrap();
+
+        let mut config_id = [0u8; 64];
+        for (i, b) in config_id.iter_mut().enumerate() {
+            *b = i as u8;
+        }
+        assert_eq!(report.config_id[..], config_id[..]);
+        assert_eq!(report.config_svn, 0x0102);
+        assert_eq!(report.isv_ext_prod_id, [0x11; 16]);
+        assert_eq!(report.isv_family_id, [0x22; 16]);
+
+        // the neighbouring fields are unaffected
+        assert_eq!(report.mr_signer, [0xBB; 32]);
+        assert_eq!(report.isv_prod_id, 1);
+        assert_eq!(report.isv_svn, 3);
+        assert_eq!(report.report_data[..], [0x33; 64][..]);
+
+        // reports without KSS leave the fields zeroed
+        let report = SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        assert_eq!(report.config_id[..], [0u8; 64][..]);
+        assert_eq!(report.config_svn, 0);
+    }
+
+    fn peer_report(owner_key: u8, mr_enclave: Option<[u8; 32]>) -> SgxEnclaveReport {
+        let mut report = self_enclave_report();
+        report.report_data[..32].copy_from_slice(&[owner_key; 32]);
+        if let Some(mr_enclave) = mr_enclave {
+            report.mr_enclave = mr_enclave;
+        }
+
+        report
+    }
+
+    pub fn test_decode_quote_body_padding() {
+        let report = attesation_report();
+        let quote_raw = base64::decode(report["isvEnclaveQuoteBody"].as_str().unwrap()).unwrap();
+
+        // The full quote body encodes without padding, so use lengths that need it
+        for len in &[quote_raw.len() - 1, quote_raw.len() - 2] {
+            let padded = base64::encode_config(&quote_raw[..*len], base64::STANDARD);
+            let unpadded = base64::encode_config(&quote_raw[..*len], base64::STANDARD_NO_PAD);
+            assert!(padded.ends_with('='));
+            assert!(!unpadded.ends_with('='));
+
+            assert_eq!(decode_quote_body(&padded).unwrap(), &quote_raw[..*len]);
+            assert_eq!(decode_quote_body(&unpadded).unwrap(), &quote_raw[..*len]);
+        }
+
+        assert_eq!(
+            decode_quote_body("not base64!"),
+            Err(Error::ReportParseError)
+        );
+    }
+
+    pub fn test_ct_eq() {
+        let a = [0x5Au8; 32];
+        let mut b = a;
+        assert!(ct_eq(&a, &b));
+
+        for i in &[0, 15, 31] {
+            b = a;
+            b[*i] ^= 1;
+            assert!(!ct_eq(&a, &b));
+        }
+        assert!(!ct_eq(&a, &a[..31]));
+        assert!(ct_eq(&[], &[]));
+
+        assert!(Exact(a).matches(&a));
+        assert!(!Exact(a).matches(&b));
+        assert!(AnyOf(vec![b, a]).matches(&a));
+        assert!(!AnyOf(vec![b]).matches(&a));
+
+        let attributes = SgxReportAttributes {
+            flags: SGX_FLAGS_INITTED | SGX_FLAGS_MODE64BIT,
+            xfrm: 7,
+        };
+        let debug = SgxReportAttributes {
+            flags: attributes.flags | SGX_FLAGS_DEBUG,
+            ..attributes
+        };
+        assert!(attributes.ct_eq(&attributes, !0));
+        assert!(!attributes.ct_eq(&debug, !0));
+        assert!(attributes.ct_eq(&debug, !SGX_FLAGS_DEBUG));
+        assert!(!attributes.ct_eq(
+            &SgxReportAttributes {
+                xfrm: 3,
+                ..attributes
+            },
+            !0
+        ));
+    }
+
+    pub fn test_verify_signer() {
+        let mut report = self_enclave_report();
+        report.mr_signer = [0x11; 32];
+
+        assert_eq!(report.verify_signer(&[[0x11; 32]]), Ok(()));
+        assert_eq!(report.verify_signer(&[[0x22; 32], [0x11; 32]]), Ok(()));
+        assert_eq!(
+            report.verify_signer(&[[0x22; 32]]),
+            Err(NodeAuthResult::MrSignerMismatch)
+        );
+        assert_eq!(
+            report.verify_signer(&[]),
+            Err(NodeAuthResult::MrSignerMismatch)
+        );
+    }
+
+    pub fn test_report_data_fields() {
+        let report = SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        let raw = enclave_report_bytes();
+
+        assert_eq!(report.get_report_data_tail()[..], raw[352..384]);
+        assert_eq!(
+            report.report_data_field(0..32),
+            Some(&report.get_owner_key_unchecked()[..])
+        );
+        assert_eq!(report.report_data_field(32..64), Some(&raw[352..384]));
+        assert_eq!(report.report_data_field(60..64).map(|f| f.len()), Some(4));
+
+        assert_eq!(report.report_data_field(32..65), None);
+        assert_eq!(report.report_data_field(64..70), None);
+    }
+
+    pub fn test_verify_self_report_failure() {
+        use crate::registration::attestation::{clear_cached_report, FAIL_SELF_TARGET_INFO};
+        use std::sync::atomic::Ordering;
+
+        let report = self_enclave_report();
+        assert_eq!(report.verify(), Ok(()));
+
+        clear_cached_report();
+        FAIL_SELF_TARGET_INFO.store(true, Ordering::SeqCst);
+        let result = report.verify();
+        let details = report.verify_report(&VerifyPolicy::default(), &AdvisoryIDs::default());
+        FAIL_SELF_TARGET_INFO.store(false, Ordering::SeqCst);
+
+        assert_eq!(result, Err(NodeAuthResult::InvalidSelfReport));
+        assert_eq!(details.result, Err(NodeAuthResult::InvalidSelfReport));
+        assert_eq!(details.failed_checks[0].check, "self_report");
+
+        assert_eq!(report.verify(), Ok(()));
+    }
+
+    pub fn test_get_cached_report() {
+        use crate::registration::attestation::{clear_cached_report, SELF_REPORT_CREATIONS};
+        use std::sync::atomic::Ordering;
+
+        clear_cached_report();
+        let creations = SELF_REPORT_CREATIONS.load(Ordering::SeqCst);
+
+        let first = get_cached_report().unwrap();
+        let second = get_cached_report().unwrap();
+        assert_eq!(first.body.mr_enclave.m, second.body.mr_enclave.m);
+        assert_eq!(first.body.mr_signer.m, second.body.mr_signer.m);
+        assert_eq!(first.body.isv_svn, second.body.isv_svn);
+
+        let report = self_enclave_report();
+        assert_eq!(report.verify(), Ok(()));
+        assert_eq!(report.verify(), Ok(()));
+
+        assert_eq!(SELF_REPORT_CREATIONS.load(Ordering::SeqCst), creations + 1);
+    }
+
+    #[cfg(all(feature = "mock", not(feature = "SGX_MODE_HW")))]
+    fn mock_self_report() -> sgx_report_t {
+        let mut report = sgx_report_t::default();
+        report.body.mr_enclave.m = [0x5a; 32];
+        report.body.attributes.flags = SGX_FLAGS_MODE64BIT;
+        report.body.attributes.xfrm = 0x7;
+        report
+    }
+
+    #[cfg(all(feature = "mock", not(feature = "SGX_MODE_HW")))]
+    pub fn test_verify_mock_report_match() {
+        use crate::registration::attestation::set_mock_report;
+
+        set_mock_report(mock_self_report());
+        let report = self_enclave_report();
+        let result = report.verify();
+        set_mock_report(sgx_report_t::default());
+
+        assert_eq!(report.mr_enclave, [0x5a; 32]);
+        assert_eq!(result, Ok(()));
+    }
+
+    #[cfg(all(feature = "mock", not(feature = "SGX_MODE_HW")))]
+    pub fn test_verify_mock_report_mismatch() {
+        use crate::registration::attestation::set_mock_report;
+
+        set_mock_report(mock_self_report());
+        let mut other_enclave = self_enclave_report();
+        other_enclave.mr_enclave = [0xa5; 32];
+        let mut other_attributes = self_enclave_report();
+        other_attributes.attributes.xfrm = 0x3;
+        let results = (other_enclave.verify(), other_attributes.verify());
+        set_mock_report(sgx_report_t::default());
+
+        assert_eq!(results.0, Err(NodeAuthResult::MrEnclaveMismatch));
+        assert_eq!(results.1, Err(NodeAuthResult::MrEnclaveMismatch));
+    }
+
+    pub fn test_verify_with_min_cpu_svn() {
+        let mut report = self_enclave_report();
+        report.cpu_svn = [5; 16];
+
+        // equal
+        assert_eq!(report.verify_with_min_cpu_svn([5; 16]), Ok(()));
+
+        // strictly greater in every component
+        assert_eq!(report.verify_with_min_cpu_svn([4; 16]), Ok(()));
+
+        // greater in one component, lower in another
+        let mut min = [5; 16];
+        min[0] = 1;
+        min[7] = 6;
+        assert_eq!(
+            report.verify_with_min_cpu_svn(min),
+            Err(NodeAuthResult::CpuSvnTooLow)
+        );
+
+        // the enclave checks still apply
+        report.mr_enclave = [0xFF; 32];
+        assert_eq!(
+            report.verify_with_min_cpu_svn([0; 16]),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+    }
+
+    pub fn test_epid_quote_sig_type() {
+        assert_eq!(
+            SgxEpidQuoteSigType::from_u16(0),
+            Ok(SgxEpidQuoteSigType::Unlinkable)
+        );
+        assert_eq!(
+            SgxEpidQuoteSigType::from_u16(1),
+            Ok(SgxEpidQuoteSigType::Linkable)
+        );
+        assert_eq!(
+            SgxEpidQuoteSigType::from_u16(2),
+            Err(Error::ReportParseError)
+        );
+
+        assert_eq!(SgxEpidQuoteSigType::Unlinkable.as_u16(), 0);
+        assert_eq!(SgxEpidQuoteSigType::Linkable.as_u16(), 1);
+    }
+
+    pub fn test_verify_attributes_masked() {
+        let report = self_enclave_report();
+        assert_eq!(report.verify_attributes_masked(!0, !0), Ok(()));
+
+        let mut other = report.clone();
+        other.attributes.xfrm ^= XfrmFeatures::AVX512.bits();
+        assert_eq!(
+            other.verify_attributes_masked(!0, !0),
+            Err(NodeAuthResult::AttributesMismatch)
+        );
+        assert_eq!(
+            other.verify_attributes_masked(SECURITY_FLAGS_MASK, !XfrmFeatures::AVX512.bits()),
+            Ok(())
+        );
+        assert_eq!(
+            other.verify_attributes_masked(SECURITY_FLAGS_MASK, 0),
+            Ok(())
+        );
+
+        let mut other = report.clone();
+        other.attributes.flags ^= SGX_FLAGS_DEBUG;
+        assert_eq!(
+            other.verify_attributes_masked(SECURITY_FLAGS_MASK, 0),
+            Err(NodeAuthResult::AttributesMismatch)
+        );
+
+        // flags outside of the mask are ignored
+        let mut other = report.clone();
+        other.attributes.flags ^= SGX_FLAGS_PROVISION_KEY;
+        assert_eq!(
+            other.verify_attributes_masked(SECURITY_FLAGS_MASK, !0),
+            Ok(())
+        );
+    }
+
+    pub fn test_is_self_report() {
+        let report = self_enclave_report();
+        assert!(report.is_self_report());
+
+        // report_data is what we bind into the report, not our identity
+        let mut other = report.clone();
+        other.report_data = [0x42; 64];
+        assert!(other.is_self_report());
+
+        // the same enclave on another platform isn't us
+        let mut other = report.clone();
+        other.cpu_svn[0] ^= 1;
+        assert!(other.verify().is_ok());
+        assert!(!other.is_self_report());
+
+        let mut other = report.clone();
+        other.mr_enclave[0] ^= 1;
+        assert!(!other.is_self_report());
+
+        let mut other = report;
+        other.isv_svn = other.isv_svn.wrapping_add(1);
+        assert!(!other.is_self_report());
+    }
+
+    pub fn test_report_diff() {
+        let report = self_enclave_report();
+        assert!(report.diff(&report.clone()).is_empty());
+
+        let mut other = report.clone();
+        other.mr_enclave[0] ^= 1;
+        other.isv_svn = other.isv_svn.wrapping_add(1);
+        assert_eq!(
+            report.diff(&other),
+            vec![ReportFieldDiff::MrEnclave, ReportFieldDiff::IsvSvn]
+        );
+        assert_eq!(other.diff(&report), report.diff(&other));
+
+        other.extra.push(0);
+        other.attributes.flags ^= SGX_FLAGS_DEBUG;
+        assert_eq!(
+            report.diff(&other),
+            vec![
+                ReportFieldDiff::Attributes,
+                ReportFieldDiff::MrEnclave,
+                ReportFieldDiff::IsvSvn,
+                ReportFieldDiff::Extra
+            ]
+        );
+    }
+
+    pub fn test_same_platform_as() {
+        let report = self_enclave_report();
+        let mut other = peer_report(1, Some([0xaa; 32]));
+        assert!(report.same_platform_as(&other));
+
+        other.cpu_svn[15] ^= 1;
+        assert!(!report.same_platform_as(&other));
+        assert!(!other.same_platform_as(&report));
+
+        let mut other = report.clone();
+        other.attributes.xfrm ^= 0x04;
+        assert!(!report.same_platform_as(&other));
+    }
+
+    pub fn test_verify_same_product() {
+        let self_prod_id = get_cached_report().unwrap().body.isv_prod_id;
+
+        let mut report = self_enclave_report();
+        assert_eq!(report.verify_same_product(), Ok(()));
+
+        report.isv_prod_id = self_prod_id.wrapping_add(1);
+        assert_eq!(
+            report.verify_same_product(),
+            Err(NodeAuthResult::IsvProdIdMismatch)
+        );
+    }
+
+    pub fn test_verify_misc_select() {
+        let mut report = self_enclave_report();
+        report.misc_select = 0;
+        assert_eq!(report.verify_misc_select(0), Ok(()));
+
+        report.misc_select = 1;
+        assert_eq!(
+            report.verify_misc_select(0),
+            Err(NodeAuthResult::MiscSelectMismatch)
+        );
+
+        let policy = VerifyPolicy {
+            misc_select: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(
+            report.verify_with_policy(&policy),
+            Err(NodeAuthResult::MiscSelectMismatch)
+        );
+        let verify_report = report.verify_report(&policy, &AdvisoryIDs(vec![]));
+        assert_eq!(
+            verify_report.failed_checks,
+            vec![FailedCheck {
+                check: "misc_select",
+                got: "0x1".to_string(),
+                expected: "0x0".to_string(),
+            }]
+        );
+
+        // not checked unless required
+        assert_eq!(report.verify_with_policy(&VerifyPolicy::default()), Ok(()));
+    }
+
+    pub fn test_verify_prod_id() {
+        let mut report = self_enclave_report();
+        report.isv_prod_id = 4;
+
+        assert_eq!(report.verify_prod_id(4), Ok(()));
+        assert_eq!(
+            report.verify_prod_id(5),
+            Err(NodeAuthResult::IsvProdIdMismatch)
+        );
+
+        let policy = VerifyPolicy {
+            isv_prod_id: Some(4),
+            ..Default::default()
+        };
+        assert_eq!(report.verify_with_policy(&policy), Ok(()));
+
+        let policy = VerifyPolicy {
+            isv_prod_id: Some(5),
+            ..Default::default()
+        };
+        assert_eq!(
+            report.verify_with_policy(&policy),
+            Err(NodeAuthResult::IsvProdIdMismatch)
+        );
+        let details = report.verify_report(&policy, &AdvisoryIDs::default());
+        assert_eq!(
+            details.failed_checks,
+            vec![FailedCheck {
+                check: "isv_prod_id",
+                got: "4".to_string(),
+                expected: "5".to_string(),
+            }]
+        );
+    }
+
+    pub fn test_verify_policy_fields() {
+        let mut report = self_enclave_report();
+        report.isv_prod_id = 4;
+        report.isv_svn = 3;
+        report.mr_signer = [0xBB; 32];
+        report.cpu_svn = [5; 16];
+
+        let satisfied = || VerifyPolicy {
+            min_isv_svn: 3,
+            isv_prod_id: Some(4),
+            allowed_signers: vec![[0xAA; 32], [0xBB; 32]],
+            min_cpu_svn: Some([5; 16]),
+            ..Default::default()
+        };
+        assert_eq!(report.verify_with_policy(&satisfied()), Ok(()));
+        assert!(report
+            .verify_report(&satisfied(), &AdvisoryIDs::default())
+            .failed_checks
+            .is_empty());
+
+        // the default policy only checks the measurement and attributes
+        assert_eq!(report.verify_with_policy(&VerifyPolicy::default()), Ok(()));
+
+        let failures: Vec<(VerifyPolicy, NodeAuthResult, &str)> = vec![
+            (
+                VerifyPolicy {
+                    min_isv_svn: 4,
+                    ..satisfied()
+                },
+                NodeAuthResult::IsvSvnTooLow,
+                "isv_svn",
+            ),
+            (
+                VerifyPolicy {
+                    isv_prod_id: Some(5),
+                    ..satisfied()
+                },
+                NodeAuthResult::IsvProdIdMismatch,
+                "isv_prod_id",
+            ),
+            (
+                VerifyPolicy {
+                    allowed_signers: vec![[0xAA; 32]],
+                    ..satisfied()
+                },
+                NodeAuthResult::MrSignerMismatch,
+                "mr_signer",
+            ),
+            (
+                VerifyPolicy {
+                    min_cpu_svn: Some([6; 16]),
+                    ..satisfied()
+                },
+                NodeAuthResult::CpuSvnTooLow,
+                "cpu_svn",
+            ),
+        ];
+        for (policy, error, check) in failures {
+            assert_eq!(report.verify_with_policy(&policy), Err(error));
+            let details = report.verify_report(&policy, &AdvisoryIDs::default());
+            assert_eq!(details.failed_checks.len(), 1);
+            assert_eq!(details.failed_checks[0].check, check);
+        }
+    }
+
+    pub fn test_attribute_flag_set() {
+        let attributes = SgxReportAttributes {
+            flags: SGX_FLAGS_DEBUG | SGX_FLAGS_MODE64BIT,
+            xfrm: 3,
+        };
+
+        let flags = attributes.flag_set();
+        assert_eq!(flags, AttributeFlags::DEBUG | AttributeFlags::MODE64BIT);
+        assert!(!flags.contains(AttributeFlags::INITTED));
+
+        let debug = format!("{:?}", attributes);
+        assert!(debug.contains("DEBUG | MODE64BIT"));
+        assert!(debug.contains("0x6"));
+        assert!(!debug.contains("PROVISION_KEY"));
+
+        // unknown bits are only kept in the raw flags
+        let attributes = SgxReportAttributes {
+            flags: SGX_FLAGS_KSS | 0x100,
+            xfrm: 0,
+        };
+        assert_eq!(attributes.flag_set(), AttributeFlags::KSS);
+        assert!(format!("{:?}", attributes).contains("0x180"));
+    }
+
+    pub fn test_xfrm_features() {
+        // x87, SSE and AVX, the usual legacy + AVX configuration
+        let attributes = SgxReportAttributes {
+            flags: 0,
+            xfrm: 0x7,
+        };
+        assert_eq!(
+            attributes.xfrm_features(),
+            XfrmFeatures::X87 | XfrmFeatures::SSE | XfrmFeatures::AVX
+        );
+        assert!(attributes.has_avx());
+        assert!(!attributes.has_avx512());
+
+        // with AVX-512 and PKRU
+        let attributes = SgxReportAttributes {
+            flags: 0,
+            xfrm: 0x2e7,
+        };
+        let features = attributes.xfrm_features();
+        assert!(features.contains(XfrmFeatures::AVX512 | XfrmFeatures::PKRU));
+        assert!(!features.contains(XfrmFeatures::MPX_BNDREGS));
+        assert!(attributes.has_avx512());
+
+        // AVX-512 needs all of its state components
+        let attributes = SgxReportAttributes {
+            flags: 0,
+            xfrm: 0x67,
+        };
+        assert!(attributes.has_avx());
+        assert!(!attributes.has_avx512());
+    }
+
+    pub fn test_verify_report_data_contains() {
+        let mut report = self_enclave_report();
+        report.report_data[8..24].copy_from_slice(&[0x42; 16]);
+
+        assert_eq!(report.verify_report_data_contains(&[0x42; 16], 8), Ok(()));
+        assert_eq!(report.verify_report_data_contains(&[], 64), Ok(()));
+
+        assert_eq!(
+            report.verify_report_data_contains(&[0x42; 16], 9),
+            Err(NodeAuthResult::ReportDataMismatch)
+        );
+        assert_eq!(
+            report.verify_report_data_contains(&[0x43; 16], 8),
+            Err(NodeAuthResult::ReportDataMismatch)
+        );
+
+        assert_eq!(
+            report.verify_report_data_contains(&[0x42; 16], 56),
+            Err(NodeAuthResult::ReportDataOutOfRange)
+        );
+        assert_eq!(
+            report.verify_report_data_contains(&[0x42; 16], usize::MAX),
+            Err(NodeAuthResult::ReportDataOutOfRange)
+        );
+    }
+
+    pub fn test_report_data_str() {
+        let mut report = self_enclave_report();
+        report.report_data = [0u8; 64];
+        report.report_data[40..47].copy_from_slice(b"v1:tag\0");
+        report.report_data[47] = b'!';
+
+        assert_eq!(report.report_data_str(40..46), Ok("v1:tag"));
+        assert_eq!(report.report_data_str(48..64), Ok(""));
+        // only trailing nulls are trimmed
+        assert_eq!(report.report_data_str(40..64), Ok("v1:tag\0!"));
+
+        assert_eq!(report.report_data_str(40..65), Err(Error::ReportParseError));
+
+        report.report_data[48..50].copy_from_slice(&[0xc3, 0x28]);
+        assert_eq!(report.report_data_str(48..64), Err(Error::ReportParseError));
+    }
+
+    pub fn test_report_data_is_empty() {
+        let mut report = self_enclave_report();
+        report.report_data = [0u8; 64];
+        assert!(report.report_data_is_empty());
+
+        for i in [0, 31, 63] {
+            let mut report = report.clone();
+            report.report_data[i] = 1;
+            assert!(!report.report_data_is_empty());
+        }
+    }
+
+    pub fn test_node_auth_result_codes() {
+        let mut codes = std::collections::HashSet::new();
+        for &result in NodeAuthResult::ALL {
+            let code = result.as_code();
+            assert!(codes.insert(code), "code {} is used twice", code);
+            assert_eq!(NodeAuthResult::from_code(code), Some(result));
+        }
+
+        // the variants that predate the codes keep their original values
+        assert_eq!(NodeAuthResult::Success.as_code(), 0);
+        assert_eq!(NodeAuthResult::Panic.as_code(), 19);
+        assert_eq!(NodeAuthResult::Panic as u32, 19);
+
+        assert_eq!(NodeAuthResult::from_code(codes.len() as u32), None);
+        assert_eq!(NodeAuthResult::from_code(u32::MAX), None);
+    }
+
+    pub fn test_parse_from_little_endian() {
+        // body offsets of the fields, see parse_from
+        let mut raw = vec![0u8; REPORT_BODY_LEN];
+        raw[16..20].copy_from_slice(&[0x04, 0x03, 0x02, 0x01]);
+        raw[48..56].copy_from_slice(&[0x08, 0x07, 0x06, 0x05, 0x04, 0x03, 0x02, 0x01]);
+        raw[56..64].copy_from_slice(&[0x18, 0x17, 0x16, 0x15, 0x14, 0x13, 0x12, 0x11]);
+        raw[256..258].copy_from_slice(&[0x02, 0x01]);
+        raw[258..260].copy_from_slice(&[0x04, 0x03]);
+        raw[260..262].copy_from_slice(&[0x06, 0x05]);
+
+        let report = SgxEnclaveReport::parse_from(&raw).unwrap();
+        assert_eq!(report.misc_select, 0x01020304);
+        assert_eq!(report.attributes.flags, 0x0102030405060708);
+        assert_eq!(report.attributes.xfrm, 0x1112131415161718);
+        assert_eq!(report.isv_prod_id, 0x0102);
+        assert_eq!(report.isv_svn, 0x0304);
+        assert_eq!(report.config_svn, 0x0506);
+        assert_eq!(report.to_bytes(), raw);
+
+        let view = SgxEnclaveReportRef::parse_from(&raw).unwrap();
+        assert_eq!(view.misc_select(), report.misc_select);
+        assert_eq!(view.attributes(), report.attributes);
+        assert_eq!(view.isv_prod_id(), report.isv_prod_id);
+        assert_eq!(view.isv_svn(), report.isv_svn);
+        assert_eq!(view.config_svn(), report.config_svn);
+    }
+
+    pub fn test_parse_from_quiet() {
+        let raw = enclave_report_bytes();
+
+        for len in [0, 1, REPORT_BODY_LEN - 1] {
+            let quiet = SgxEnclaveReport::parse_from_quiet(&raw[..len]).err();
+            assert_eq!(
+                quiet,
+                Some(Error::ReportTruncated {
+                    expected: REPORT_BODY_LEN,
+                    got: len
+                })
+            );
+            assert_eq!(quiet, SgxEnclaveReport::parse_from(&raw[..len]).err());
+        }
+
+        assert_eq!(
+            SgxEnclaveReport::parse_from_quiet(&raw).unwrap(),
+            SgxEnclaveReport::parse_from(&raw).unwrap()
+        );
+    }
+
+    pub fn test_prelude() {
+        use crate::registration::prelude::{
+            AdvisoryIDs as PreludeAdvisoryIDs, AttestationReport as PreludeAttestationReport,
+            NodeAuthResult as PreludeNodeAuthResult, SgxEnclaveReport as PreludeSgxEnclaveReport,
+            SgxQuote as PreludeSgxQuote, SgxQuoteStatus as PreludeSgxQuoteStatus,
+        };
+
+        // the prelude re-exports the types themselves, not copies
+        let report: PreludeSgxEnclaveReport =
+            SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        assert_eq!(
+            report.verify_prod_id(u16::MAX).err(),
+            Some(PreludeNodeAuthResult::IsvProdIdMismatch)
+        );
+        assert_eq!(PreludeSgxQuoteStatus::from("OK"), SgxQuoteStatus::OK);
+        assert_eq!("".parse::<PreludeAdvisoryIDs>(), Ok(AdvisoryIDs::default()));
+
+        let attestation: PreludeAttestationReport =
+            AttestationReport::from_cert(&tls_ra_cert_der_v4()).unwrap();
+        let quote: &PreludeSgxQuote = attestation.quote();
+        assert_eq!(quote.isv_svn_qe, attestation.sgx_quote_body.isv_svn_qe);
+    }
+
+    pub fn test_with_report_data() {
+        let full: Vec<u8> = (0..64).collect();
+        let report_data = SgxEnclaveReport::with_report_data(&full).unwrap();
+        assert_eq!(&report_data[..], &full[..]);
+
+        let report_data = SgxEnclaveReport::with_report_data(&[0xab; 20]).unwrap();
+        assert_eq!(report_data[..20], [0xab; 20]);
+        assert_eq!(report_data[20..], [0; 44]);
+
+        assert_eq!(SgxEnclaveReport::with_report_data(&[]), Ok([0; 64]));
+
+        assert_eq!(
+            SgxEnclaveReport::with_report_data(&[0xab; 65]),
+            Err(Error::ReportDataTooLong(65))
+        );
+    }
+
+    pub fn test_to_report_body() {
+        let report = SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        let body = report.to_report_body();
+
+        assert_eq!(body.mr_enclave.m, report.mr_enclave);
+        assert_eq!(body.isv_svn, report.isv_svn);
+
+        // the native struct has the layout of the serialized report
+        let raw = unsafe {
+            std::slice::from_raw_parts(
+                &body as *const sgx_report_body_t as *const u8,
+                std::mem::size_of::<sgx_report_body_t>(),
+            )
+        };
+        assert_eq!(raw, &report.to_bytes()[..]);
+    }
+
+    pub fn test_parse_from_at() {
+        let raw = enclave_report_bytes();
+        let expected = SgxEnclaveReport::parse_from(&raw[..REPORT_BODY_LEN]).unwrap();
+
+        // a report between a header and a trailer, as in a quote
+        let mut message = vec![0xee; 48];
+        message.extend_from_slice(&raw[..REPORT_BODY_LEN]);
+        message.extend_from_slice(&[0xab, 0xcd]);
+
+        let (report, next) = SgxEnclaveReport::parse_from_at(&message, 48).unwrap();
+        assert_eq!(report, expected);
+        assert_eq!(next, 48 + REPORT_BODY_LEN);
+        assert!(report.extra.is_empty());
+        assert_eq!(&message[next..], &[0xab, 0xcd]);
+
+        // the trailer is kept as extra only by parse_from
+        let (at_start, next) = SgxEnclaveReport::parse_from_at(&raw, 0).unwrap();
+        assert_eq!(next, REPORT_BODY_LEN);
+        assert_eq!(
+            at_start,
+            SgxEnclaveReport::parse_from(&raw[..REPORT_BODY_LEN]).unwrap()
+        );
+
+        assert_eq!(
+            SgxEnclaveReport::parse_from_at(&message, 51).unwrap_err(),
+            Error::ReportTruncated {
+                expected: 51 + REPORT_BODY_LEN,
+                got: message.len()
+            }
+        );
+        assert_eq!(
+            SgxEnclaveReport::parse_from_at(&message, usize::MAX).unwrap_err(),
+            Error::ReportTruncated {
+                expected: usize::MAX,
+                got: message.len()
+            }
+        );
+    }
+
+    #[cfg(feature = "report-serde")]
+    pub fn test_enclave_report_serde() {
+        let mut raw = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/enclave_report_kss.bin").unwrap();
+        f.read_to_end(&mut raw).unwrap();
+        raw.extend_from_slice(&[0xab, 0xcd]);
+        let report = SgxEnclaveReport::parse_from(&raw).unwrap();
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["mr_enclave"], hex::encode(report.mr_enclave));
+        assert_eq!(json["extra"], "abcd");
+        assert_eq!(json["attributes"]["flags"], report.attributes.flags);
+
+        let decoded: SgxEnclaveReport = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(decoded, report);
+
+        // byte arrays must have their exact length
+        let mut short = json;
+        short["mr_signer"] = Value::from(hex::encode([0u8; 31]));
+        assert!(serde_json::from_value::<SgxEnclaveReport>(short).is_err());
+    }
+
+    pub fn test_sgx_quote_verify_qe_svn() {
+        let attn_report = attesation_report();
+        let sgx_quote_body_encoded = attn_report["isvEnclaveQuoteBody"].as_str().unwrap();
+        let quote_raw = base64::decode(&sgx_quote_body_encoded.as_bytes()).unwrap();
+        let sgx_quote = SgxQuote::parse_from(quote_raw.as_slice()).unwrap();
+
+        // the quote is at QE svn 10 and PCE svn 9
+        assert_eq!(sgx_quote.verify_qe_svn(0, 0), Ok(()));
+        assert_eq!(sgx_quote.verify_qe_svn(10, 9), Ok(()));
+        assert_eq!(
+            sgx_quote.verify_qe_svn(11, 9),
+            Err(NodeAuthResult::QeSvnTooLow)
+        );
+        assert_eq!(
+            sgx_quote.verify_qe_svn(10, 10),
+            Err(NodeAuthResult::PceSvnTooLow)
+        );
+        // the QE is checked first
+        assert_eq!(
+            sgx_quote.verify_qe_svn(11, 10),
+            Err(NodeAuthResult::QeSvnTooLow)
+        );
+    }
+
+    pub fn test_node_auth_result_to_result() {
+        assert_eq!(NodeAuthResult::Success.to_result(), Ok(()));
+        assert_eq!(NodeAuthResult::from(Ok(())), NodeAuthResult::Success);
+
+        // every variant other than Success, as numbered by as_code
+        let mut count = 0;
+        for error in (1..).map_while(NodeAuthResult::from_code) {
+            let code = error.as_code();
+            let result = error.to_result();
+            assert_eq!(result.as_ref().map_err(NodeAuthResult::as_code), Err(code));
+            assert_eq!(NodeAuthResult::from(result).as_code(), code);
+            count += 1;
+        }
+        assert!(count >= NodeAuthResult::AttributesMismatch.as_code());
+    }
+
+    pub fn test_enclave_report_eq() {
+        let mut raw = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/enclave_report_kss.bin").unwrap();
+        f.read_to_end(&mut raw).unwrap();
+
+        let report = SgxEnclaveReport::parse_from(&raw).unwrap();
+        assert_eq!(report, SgxEnclaveReport::parse_from(&raw).unwrap());
+
+        // flipping any byte of the body, including reserved ones that aren't
+        // kept, only changes equality if it's part of a field
+        let reserved: Vec<usize> = REPORT_BODY_RESERVED
+            .iter()
+            .flat_map(|&(offset, len)| offset..offset + len)
+            .collect();
+        for i in 0..raw.len() {
+            let mut changed = raw.clone();
+            changed[i] ^= 0x01;
+            let other = SgxEnclaveReport::parse_from(&changed).unwrap();
+            assert_eq!(report == other, reserved.contains(&i), "byte {}", i);
+        }
+
+        let mut extended = raw.clone();
+        extended.push(0);
+        assert_ne!(report, SgxEnclaveReport::parse_from(&extended).unwrap());
+
+        let attributes = report.attributes;
+        assert_eq!(attributes, report.attributes);
+        assert_ne!(
+            attributes,
+            SgxReportAttributes {
+                flags: attributes.flags ^ SGX_FLAGS_DEBUG,
+                ..attributes
+            }
+        );
+    }
+
+    pub fn test_enclave_report_clone() {
+        let mut report = peer_report(1, None);
+        report.extra = vec![1, 2, 3];
+
+        let mut clone = report.clone();
+        assert_eq!(clone, report);
+
+        clone.isv_svn += 1;
+        clone.extra.push(4);
+        assert_ne!(clone, report);
+        assert_eq!(report.extra, vec![1, 2, 3]);
+    }
+
+    pub fn test_epid_group_id() {
+        let attn_report = attesation_report();
+        let quote_raw =
+            base64::decode(attn_report["isvEnclaveQuoteBody"].as_str().unwrap()).unwrap();
+        let gid = SgxQuote::parse_from(&quote_raw).unwrap().gid;
+
+        assert_eq!(gid, EpidGroupId(2863));
+        assert_eq!(gid.as_u32(), 2863);
+        assert_eq!(format!("{:?}", gid), "00000b2f");
+        assert_eq!(format!("{:?}", EpidGroupId(0xc12)), "00000c12");
+    }
+
+    pub fn test_verify_against_measurements() {
+        let old = [0xAA; 32];
+        let new = [0xBB; 32];
+
+        let mut report = self_enclave_report();
+        report.mr_enclave = old;
+        assert_eq!(report.verify_against_measurements(&[old, new]), Ok(()));
+
+        report.mr_enclave = new;
+        assert_eq!(report.verify_against_measurements(&[old, new]), Ok(()));
+
+        report.mr_enclave = [0xCC; 32];
+        assert_eq!(
+            report.verify_against_measurements(&[old, new]),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+        assert_eq!(
+            report.verify_against_measurements(&[]),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+
+        // the attributes are still those of this enclave
+        report.mr_enclave = new;
+        report.attributes.flags ^= SGX_FLAGS_DEBUG;
+        assert_eq!(
+            report.verify_against_measurements(&[old, new]),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+    }
+
+    pub fn test_enclave_report_fingerprint() {
+        let report = peer_report(1, None);
+        let fingerprint = report.fingerprint();
+        assert_eq!(fingerprint.len(), 16);
+        assert_eq!(fingerprint, report.clone().fingerprint());
+        assert_eq!(
+            fingerprint,
+            SgxEnclaveReport::parse_from(&report.to_bytes())
+                .unwrap()
+                .fingerprint()
+        );
+        assert_eq!(fingerprint, hex::encode(&sha_256(&report.to_bytes())[..8]));
+
+        let mut changed = report.clone();
+        changed.isv_svn += 1;
+        assert_ne!(changed.fingerprint(), fingerprint);
+    }
+
+    pub fn test_verify_not_debug() {
+        let mut report = self_enclave_report();
+        report.attributes.flags = (AttributeFlags::INITTED | AttributeFlags::MODE64BIT).bits();
+        assert_eq!(report.verify_not_debug(), Ok(()));
+
+        report.attributes.flags |= AttributeFlags::DEBUG.bits();
+        assert_eq!(
+            report.verify_not_debug(),
+            Err(NodeAuthResult::DebugEnclaveNotAllowed)
+        );
+    }
+
+    pub fn test_enclave_report_ref() {
+        let mut raw = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/enclave_report_kss.bin").unwrap();
+        f.read_to_end(&mut raw).unwrap();
+
+        let owned = SgxEnclaveReport::parse_from(&raw).unwrap();
+        let view = SgxEnclaveReportRef::parse_from(&raw).unwrap();
+
+        assert_eq!(view.cpu_svn(), &owned.cpu_svn);
+        assert_eq!(view.misc_select(), owned.misc_select);
+        assert_eq!(view.isv_ext_prod_id(), &owned.isv_ext_prod_id);
+        assert_eq!(view.attributes(), owned.attributes);
+        assert_eq!(view.mr_enclave(), &owned.mr_enclave);
+        assert_eq!(view.mr_signer(), &owned.mr_signer);
+        assert_eq!(view.config_id()[..], owned.config_id[..]);
+        assert_eq!(view.isv_prod_id(), owned.isv_prod_id);
+        assert_eq!(view.isv_svn(), owned.isv_svn);
+        assert_eq!(view.config_svn(), owned.config_svn);
+        assert_eq!(view.isv_family_id(), &owned.isv_family_id);
+        assert_eq!(view.report_data()[..], owned.report_data[..]);
+        assert!(view.extra().is_empty());
+        assert_eq!(view.to_report(), owned);
+
+        // the accessors borrow from the input
+        assert_eq!(view.mr_enclave().as_ptr(), raw[64..].as_ptr());
+
+        let mut extended = raw.clone();
+        extended.extend_from_slice(&[1, 2, 3]);
+        let view = SgxEnclaveReportRef::parse_from(&extended).unwrap();
+        assert_eq!(view.extra(), &[1, 2, 3]);
+        assert_eq!(
+            view.to_report(),
+            SgxEnclaveReport::parse_from(&extended).unwrap()
+        );
+
+        assert_eq!(
+            SgxEnclaveReportRef::parse_from(&raw[..100]).err(),
+            Some(Error::ReportTruncated {
+                expected: REPORT_BODY_LEN,
+                got: 100
+            })
+        );
+
+        // verification gives the same outcome as on the owned report
+        let report = self_enclave_report();
+        let bytes = report.to_bytes();
+        let view = SgxEnclaveReportRef::parse_from(&bytes).unwrap();
+        assert_eq!(view.verify(), Ok(()));
+
+        let policy = VerifyPolicy {
+            min_isv_svn: report.isv_svn + 1,
+            ..Default::default()
+        };
+        assert_eq!(
+            view.verify_with_policy(&policy),
+            report.verify_with_policy(&policy)
+        );
+        assert_eq!(
+            view.verify_with_policy(&policy),
+            Err(NodeAuthResult::IsvSvnTooLow)
+        );
+    }
+
+    pub fn test_sgx_quote_parse_v1() {
+        let attn_report = attesation_report();
+        let mut quote_raw =
+            base64::decode(attn_report["isvEnclaveQuoteBody"].as_str().unwrap()).unwrap();
+
+        // legacy EPID quotes have the same layout as version 2
+        quote_raw[0..2].copy_from_slice(&1u16.to_le_bytes());
+        quote_raw[2..4].copy_from_slice(&0u16.to_le_bytes());
+        let quote = SgxQuote::parse_from(&quote_raw).unwrap();
+        assert_eq!(
+            quote.version,
+            SgxQuoteVersion::V1(SgxEpidQuoteSigType::Unlinkable)
+        );
+        assert_eq!(quote.gid, EpidGroupId(2863));
+        assert_eq!(quote.att_key_type(), None);
+
+        quote_raw[0..2].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(
+            SgxQuote::parse_from(&quote_raw).err(),
+            Some(Error::UnsupportedQuoteVersion(0))
+        );
+    }
+
+    pub fn test_verify_with_min_svn() {
+        let mut report = self_enclave_report();
+        report.isv_svn = 3;
+
+        assert_eq!(report.verify(), Ok(()));
+        assert_eq!(report.verify_with_min_svn(0), Ok(()));
+        assert_eq!(report.verify_with_min_svn(3), Ok(()));
+        assert_eq!(
+            report.verify_with_min_svn(4),
+            Err(NodeAuthResult::IsvSvnTooLow)
+        );
+
+        // the measurement check still comes first
+        let report = peer_report(1, Some([0xAA; 32]));
+        assert_eq!(
+            report.verify_with_min_svn(0),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+    }
+
+    pub fn test_get_owner_key() {
+        let report = peer_report(7, None);
+        assert_eq!(report.get_owner_key(), Ok([7u8; 32]));
+        assert_eq!(report.get_owner_key_unchecked(), [7u8; 32]);
+
+        let report = peer_report(0, None);
+        assert_eq!(report.get_owner_key(), Err(Error::EmptyOwnerKey));
+        assert_eq!(report.get_owner_key_unchecked(), [0u8; 32]);
+
+        // a key only has to be non-zero somewhere
+        let mut report = peer_report(0, None);
+        report.report_data[31] = 1;
+        assert!(report.get_owner_key().is_ok());
+    }
+
+    pub fn test_set_owner_key() {
+        let mut report = peer_report(7, None);
+        report.report_data[32..].copy_from_slice(&[9u8; 32]);
+
+        report.set_owner_key(&[3u8; 32]);
+        assert_eq!(report.get_owner_key(), Ok([3u8; 32]));
+        assert_eq!(report.get_report_data_tail(), [9u8; 32]);
+        assert_eq!(report.to_bytes()[320..352], [3u8; 32]);
+    }
+
+    pub fn test_get_keys() {
+        let mut report = peer_report(7, None);
+        report.report_data[32..].copy_from_slice(&[9u8; 32]);
+        assert_eq!(report.get_keys(), Ok(([7u8; 32], [9u8; 32])));
+
+        report.report_data[32..].copy_from_slice(&[0u8; 32]);
+        assert_eq!(report.get_keys(), Err(Error::EmptyEncryptionKey));
+
+        let mut report = peer_report(0, None);
+        report.report_data[32..].copy_from_slice(&[9u8; 32]);
+        assert_eq!(report.get_keys(), Err(Error::EmptyOwnerKey));
+    }
+
+    pub fn test_verify_transcript() {
+        let key = [7u8; 32];
+        let challenge = [42u8; 32];
+
+        let mut report = self_enclave_report();
+        report.report_data[..32].copy_from_slice(&key);
+        report.report_data[32..].copy_from_slice(&sha_256(&challenge));
+
+        assert_eq!(report.verify_transcript(&key, &challenge), Ok(()));
+        assert_eq!(
+            report.verify_transcript(&[8u8; 32], &challenge),
+            Err(NodeAuthResult::ReportDataMismatch)
+        );
+        assert_eq!(
+            report.verify_transcript(&key, &[43u8; 32]),
+            Err(NodeAuthResult::ReportDataMismatch)
+        );
+    }
+
+    pub fn test_sgx_quote_status_policy() {
+        let status = SgxQuoteStatus::from("KEY_REVOKED");
+        assert_eq!(status, SgxQuoteStatus::KeyRevoked);
+        assert!(status.is_fatal());
+        assert!(!status.is_retryable());
+        assert_eq!(NodeAuthResult::from(&status), NodeAuthResult::KeyRevoked);
+
+        let status = SgxQuoteStatus::from("SIGRL_VERSION_MISMATCH");
+        assert_eq!(status, SgxQuoteStatus::SigrlVersionMismatch);
+        assert!(!status.is_fatal());
+        assert!(status.is_retryable());
+        assert_eq!(
+            NodeAuthResult::from(&status),
+            NodeAuthResult::SigrlVersionMismatch
+        );
+
+        let status = SgxQuoteStatus::from("OK");
+        assert!(!status.is_fatal());
+        assert!(!status.is_retryable());
+    }
+
+    pub fn test_measurement_matchers() {
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+
+        assert!(Exact(a).matches(&a));
+        assert!(!Exact(a).matches(&b));
+
+        assert!(AnyOf(vec![a, b]).matches(&b));
+        assert!(!AnyOf(vec![a]).matches(&b));
+        assert!(!AnyOf(vec![]).matches(&a));
+
+        let revoked: HashSet<[u8; 32]> = vec![a].into_iter().collect();
+        assert!(!NotIn(revoked.clone()).matches(&a));
+        assert!(NotIn(revoked).matches(&b));
+
+        // the matcher of the policy replaces the comparison to our own mr_enclave
+        let report = peer_report(1, Some(b));
+        assert_eq!(report.verify(), Err(NodeAuthResult::MrEnclaveMismatch));
+
+        let policy = VerifyPolicy {
+            mr_enclave: Some(Box::new(AnyOf(vec![a, b]))),
+            ..Default::default()
+        };
+        assert_eq!(report.verify_with_policy(&policy), Ok(()));
+
+        let policy = VerifyPolicy {
+            mr_enclave: Some(Box::new(NotIn(vec![b].into_iter().collect()))),
+            ..Default::default()
+        };
+        assert_eq!(
+            report.verify_with_policy(&policy),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+    }
+
+    pub fn test_verify_with_hook_deadline() {
+        use std::time::Duration;
+
+        let report = peer_report(1, None);
+        let attestation = peer_attestation(peer_report(1, None));
+
+        // a hook that returns only after the deadline
+        let slow_hook = |_: &SgxEnclaveReport, _: &AttestationReport| {
+            let deadline = Instant::now() + Duration::from_millis(20);
+            while Instant::now() <= deadline {}
+            Ok(())
+        };
+
+        let policy = VerifyPolicy {
+            deadline: Some(Instant::now() + Duration::from_millis(10)),
+            ..Default::default()
+        };
+        assert_eq!(
+            verify_with_hook(&report, &attestation, &policy, slow_hook),
+            Err(NodeAuthResult::DeadlineExceeded)
+        );
+
+        // the hook isn't called once the deadline passed
+        assert_eq!(
+            verify_with_hook(&report, &attestation, &policy, |_, _| panic!(
+                "hook called after the deadline"
+            )),
+            Err(NodeAuthResult::DeadlineExceeded)
+        );
+
+        // pure checks still complete and report their own error
+        let bad_report = peer_report(1, Some([0xFF; 32]));
+        assert_eq!(
+            verify_with_hook(&bad_report, &attestation, &policy, |_, _| Ok(())),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+
+        let policy = VerifyPolicy {
+            deadline: Some(Instant::now() + Duration::from_secs(60)),
+            ..Default::default()
+        };
+        assert_eq!(
+            verify_with_hook(&report, &attestation, &policy, slow_hook),
+            Ok(())
+        );
+    }
+
+    pub fn test_fleet_tcb_summary() {
+        let attestation = |status: SgxQuoteStatus, advisories: &[&str]| {
+            let mut attestation = peer_attestation(peer_report(1, None));
+            attestation.sgx_quote_status = status;
+            attestation.advisory_ids =
+                AdvisoryIDs(advisories.iter().map(|a| a.to_string()).collect());
+            attestation
+        };
+
+        let attestations = vec![
+            attestation(SgxQuoteStatus::OK, &[]),
+            attestation(SgxQuoteStatus::OK, &[]),
+            attestation(SgxQuoteStatus::GroupOutOfDate, &["INTEL-SA-00161"]),
+            attestation(
+                SgxQuoteStatus::SwHardeningNeeded,
+                &["INTEL-SA-00161", "INTEL-SA-00334"],
+            ),
+            attestation(SgxQuoteStatus::UnknownBadStatus, &[]),
+        ];
+
+        let summary = FleetTcbSummary::from_attestations(&attestations);
+        assert_eq!(summary.total, 5);
+        assert_eq!(summary.count(SgxQuoteStatus::OK), 2);
+        assert_eq!(summary.count(SgxQuoteStatus::GroupOutOfDate), 1);
+        assert_eq!(summary.count(SgxQuoteStatus::SwHardeningNeeded), 1);
+        assert_eq!(summary.count(SgxQuoteStatus::UnknownBadStatus), 1);
+        assert_eq!(summary.count(SgxQuoteStatus::KeyRevoked), 0);
+        assert_eq!(
+            summary.advisory_ids,
+            AdvisoryIDs(vec![
+                "INTEL-SA-00161".to_string(),
+                "INTEL-SA-00334".to_string()
+            ])
+        );
+
+        let summary = FleetTcbSummary::from_attestations(&[]);
+        assert_eq!(summary.total, 0);
+        assert!(summary.advisory_ids.0.is_empty());
+    }
+
+    pub fn test_report_attributes_flags() {
+        let attributes = |flags: u64| SgxReportAttributes { flags, xfrm: 0 };
+
+        let none = attributes(0);
+        assert!(!none.is_init());
+        assert!(!none.is_debug());
+        assert!(!none.is_64bit());
+        assert!(!none.is_provision_key());
+
+        let debug = attributes(SGX_FLAGS_DEBUG);
+        assert!(debug.is_debug());
+        assert!(!debug.is_init());
+        assert!(!debug.is_64bit());
+
+        let provision_key = attributes(SGX_FLAGS_PROVISION_KEY);
+        assert!(provision_key.is_provision_key());
+        assert!(!provision_key.is_debug());
+
+        // the fixture report has flags 7: initialized, debug and 64-bit
+        let report = SgxEnclaveReport::parse_from(&enclave_report_bytes()).unwrap();
+        assert!(report.attributes.is_init());
+        assert!(report.attributes.is_debug());
+        assert!(report.attributes.is_64bit());
+        assert!(!report.attributes.is_provision_key());
+
+        let all = attributes(
+            SGX_FLAGS_INITTED | SGX_FLAGS_DEBUG | SGX_FLAGS_MODE64BIT | SGX_FLAGS_PROVISION_KEY,
+        );
+        assert!(all.is_init() && all.is_debug() && all.is_64bit() && all.is_provision_key());
+    }
+
+    pub fn test_verify_64bit() {
+        let mut report = peer_report(1, None);
+
+        report.attributes.flags = SGX_FLAGS_MODE64BIT | 0x3;
+        assert!(report.attributes.is_64bit());
+        assert_eq!(report.verify_64bit(), Ok(()));
+
+        report.attributes.flags = 0x3;
+        assert!(!report.attributes.is_64bit());
+        assert_eq!(report.verify_64bit(), Err(NodeAuthResult::Not64Bit));
+
+        let policy = VerifyPolicy {
+            require_64bit: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            report.verify_with_policy(&policy),
+            Err(NodeAuthResult::Not64Bit)
+        );
+
+        // production builds require it whatever the policy
+        #[cfg(feature = "production")]
+        assert_eq!(
+            report.verify_with_policy(&VerifyPolicy::default()),
+            Err(NodeAuthResult::Not64Bit)
+        );
+    }
+
+    pub fn test_advisory_whitelist_layered() {
+        let list = |ids: &[&str]| AdvisoryWhitelist(ids.iter().map(|a| a.to_string()).collect());
+
+        let base = list(&["INTEL-SA-00334", "INTEL-SA-00219", "INTEL-SA-00615"]);
+        let org = list(&["INTEL-SA-00334", "INTEL-SA-00219"]);
+        let local = list(&["INTEL-SA-00219"]);
+
+        // no overrides, base applies as is
+        let (merged, conflicts) = AdvisoryWhitelist::layered(&base, None, None);
+        assert_eq!(merged, base);
+        assert!(conflicts.is_empty());
+
+        // the most specific layer wins
+        let (merged, conflicts) = AdvisoryWhitelist::layered(&base, Some(&org), None);
+        assert_eq!(merged, org);
+        assert!(conflicts.is_empty());
+
+        let (merged, conflicts) = AdvisoryWhitelist::layered(&base, Some(&org), Some(&local));
+        assert_eq!(merged, local);
+        assert!(conflicts.is_empty());
+
+        let (merged, conflicts) = AdvisoryWhitelist::layered(&base, None, Some(&local));
+        assert_eq!(merged, local);
+        assert!(conflicts.is_empty());
+
+        // layers can't widen the layer below them
+        let org = list(&["INTEL-SA-00334", "INTEL-SA-00161"]);
+        let local = list(&["INTEL-SA-00334", "INTEL-SA-00219"]);
+        let (merged, conflicts) = AdvisoryWhitelist::layered(&base, Some(&org), Some(&local));
+        assert_eq!(merged, list(&["INTEL-SA-00334"]));
+        assert_eq!(
+            conflicts,
+            vec![
+                WhitelistConflict {
+                    advisory_id: "INTEL-SA-00161".to_string(),
+                    layer: WhitelistLayer::Org,
+                },
+                WhitelistConflict {
+                    advisory_id: "INTEL-SA-00219".to_string(),
+                    layer: WhitelistLayer::Local,
+                },
+            ]
+        );
+    }
+
+    pub fn test_verify_detailed_permissive() {
+        let production = VerifyPolicy {
+            reject_debug: true,
+            ..Default::default()
+        };
+
+        let report = peer_report(1, None);
+        assert_eq!(
+            report.verify_detailed(&production),
+            VerifyDetails {
+                result: Ok(()),
+                would_pass_permissive: true,
+            }
+        );
+
+        // a debug enclave only fails because of the production posture
+        let mut debug_report = peer_report(1, None);
+        debug_report.attributes.flags |= SGX_FLAGS_DEBUG;
+        assert_eq!(
+            debug_report.verify_detailed(&production),
+            VerifyDetails {
+                result: Err(NodeAuthResult::DebugEnclaveNotAllowed),
+                would_pass_permissive: true,
+            }
+        );
+
+        // a wrong measurement fails regardless of the posture
+        debug_report.mr_enclave = [0xFF; 32];
+        assert_eq!(
+            debug_report.verify_detailed(&production),
+            VerifyDetails {
+                result: Err(NodeAuthResult::MrEnclaveMismatch),
+                would_pass_permissive: false,
+            }
+        );
+    }
+
+    pub fn test_enclave_report_parse_strict() {
+        let report = SgxEnclaveReport::builder()
+            .mr_enclave([0xAA; 32])
+            .isv_svn(3)
+            .report_data([0xCC; 64])
+            .build();
+        let bytes = report.to_bytes();
+        assert!(SgxEnclaveReport::parse_from_strict(&bytes).unwrap() == report);
+
+        // a byte in the reserved region following misc_select
+        let mut tampered = bytes.clone();
+        tampered[25] = 1;
+        assert!(SgxEnclaveReport::parse_from(&tampered).unwrap() == report);
+        assert_eq!(
+            SgxEnclaveReport::parse_from_strict(&tampered).err(),
+            Some(Error::NonZeroReserved { offset: 25 })
+        );
+
+        // the last reserved region, and the bytes next to it
+        let mut tampered = bytes.clone();
+        tampered[303] = 1;
+        assert_eq!(
+            SgxEnclaveReport::parse_from_strict(&tampered).err(),
+            Some(Error::NonZeroReserved { offset: 303 })
+        );
+        let mut tampered = bytes.clone();
+        tampered[304] = 1;
+        assert!(SgxEnclaveReport::parse_from_strict(&tampered).is_ok());
+
+        // the strict parser still reports size errors first
+        assert_eq!(
+            SgxEnclaveReport::parse_from_strict(&bytes[..100]).err(),
+            Some(Error::ReportTruncated {
+                expected: REPORT_BODY_LEN,
+                got: 100
+            })
+        );
+    }
+
+    pub fn test_enclave_report_to_bytes() {
+        let mut report_data = [0u8; 64];
+        for (i, b) in report_data.iter_mut().enumerate() {
+            *b = 0xFF - i as u8;
+        }
+
+        let mut kss = SgxEnclaveReport::builder()
+            .misc_select(0xDEAD_BEEF)
+            .report_data(report_data)
+            .build();
+        kss.isv_ext_prod_id = [0x11; 16];
+        kss.config_id = [0x44; 64];
+        kss.config_svn = 0x0102;
+        kss.isv_family_id = [0x22; 16];
+
+        let mut extended = SgxEnclaveReport::builder().isv_svn(9).build();
+        extended.extra = vec![0xAB; 16];
+
+        for report in &[
+            SgxEnclaveReport::builder().build(),
+            SgxEnclaveReport::builder()
+                .cpu_svn([0xFF; 16])
+                .mr_enclave([0xFF; 32])
+                .mr_signer([0xFF; 32])
+                .isv_prod_id(0xFFFF)
+                .isv_svn(0xFFFF)
+                .report_data([0xFF; 64])
+                .build(),
+            kss,
+            extended,
+        ] {
+            let bytes = report.to_bytes();
+            assert_eq!(bytes.len(), REPORT_BODY_LEN + report.extra.len());
+            assert!(SgxEnclaveReport::parse_from(&bytes).unwrap() == *report);
+        }
+
+        // reports with zeroed reserved regions serialize back to the same bytes
+        let raw = enclave_report_bytes();
+        assert_eq!(SgxEnclaveReport::parse_from(&raw).unwrap().to_bytes(), raw);
+
+        let mut raw = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/enclave_report_kss.bin").unwrap();
+        f.read_to_end(&mut raw).unwrap();
+        assert_eq!(SgxEnclaveReport::parse_from(&raw).unwrap().to_bytes(), raw);
+    }
+
+    pub fn test_enclave_report_builder() {
+        let report = SgxEnclaveReport::builder()
+            .cpu_svn([3; 16])
+            .misc_select(1)
+            .attributes(SgxReportAttributes {
+                flags: SGX_FLAGS_INITTED | SGX_FLAGS_MODE64BIT,
+                xfrm: 7,
+            })
+            .mr_enclave([0xAA; 32])
+            .mr_signer([0xBB; 32])
+            .isv_prod_id(2)
+            .isv_svn(5)
+            .report_data([0xCC; 64])
+            .build();
+
+        assert_eq!(report.mr_enclave, [0xAA; 32]);
+        assert_eq!(report.isv_svn, 5);
+        assert!(report.extra.is_empty());
+
+        let parsed = SgxEnclaveReport::parse_from(&report.to_bytes()).unwrap();
+        assert!(parsed == report);
+
+        // unset fields are zero
+        let report = SgxEnclaveReport::builder().isv_svn(1).build();
+        assert_eq!(report.mr_signer, [0; 32]);
+        assert_eq!(report.report_data[..], [0; 64][..]);
+    }
+
+    pub fn test_verify_into() {
+        let policy = VerifyPolicy::default();
+
+        let report = peer_report(1, None);
+        let bytes = <[u8; REPORT_BODY_LEN]>::try_from(report.to_bytes()).unwrap();
+
+        let mut out = VerifyReportSummary::default();
+        assert_eq!(
+            verify_into(&bytes, &policy, &mut out),
+            NodeAuthResult::Success
+        );
+        assert_eq!(report.verify_with_policy(&policy), Ok(()));
+        assert_eq!(
+            out,
+            VerifyReportSummary {
+                mr_enclave: report.mr_enclave,
+                mr_signer: report.mr_signer,
+                isv_prod_id: report.isv_prod_id,
+                isv_svn: report.isv_svn,
+                owner_key: [1u8; 32],
+            }
+        );
+
+        // same outcome as the allocating path on failure, and out is left as is
+        let bad_report = peer_report(2, Some([0xFF; 32]));
+        let bad_bytes = <[u8; REPORT_BODY_LEN]>::try_from(bad_report.to_bytes()).unwrap();
+        let mut out = VerifyReportSummary::default();
+        assert_eq!(
+            verify_into(&bad_bytes, &policy, &mut out),
+            NodeAuthResult::MrEnclaveMismatch
+        );
+        assert_eq!(
+            bad_report.verify_with_policy(&policy),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+        assert_eq!(out, VerifyReportSummary::default());
+    }
+
+    pub fn test_verification_available() {
+        #[cfg(feature = "SGX_MODE_HW")]
+        assert!(verification_available());
+
+        #[cfg(not(feature = "SGX_MODE_HW"))]
+        assert!(!verification_available());
+    }
+
+    pub fn test_verify_report_support_json() {
+        let mut report = peer_report(0xAB, Some([0xFF; 32]));
+        report.isv_svn = 1;
+        let policy = VerifyPolicy {
+            min_isv_svn: 2,
+            ..Default::default()
         };
+        let advisories = AdvisoryIDs(vec!["INTEL-SA-00334".to_string()]);
 
-        let advisories: Vec<String> = if let Some(raw) = attn_report.get("advisoryIDs") {
-            serde_json::from_value(raw.clone()).map_err(|_| {
-                warn!("Failed to decode advisories");
-                Error::ReportParseError
-            })?
-        } else {
-            vec![]
+        let verify_report = report.verify_report(&policy, &advisories);
+        assert_eq!(verify_report.result, Err(NodeAuthResult::MrEnclaveMismatch));
+
+        let json = verify_report.to_support_json();
+        let parsed: Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            parsed["outcome"],
+            format!("failure: {}", NodeAuthResult::MrEnclaveMismatch)
+        );
+        let failed_checks = parsed["failed_checks"].as_array().unwrap();
+        assert_eq!(failed_checks.len(), 2);
+        assert_eq!(failed_checks[0]["check"], "mr_enclave");
+        assert_eq!(failed_checks[0]["got"], hex::encode([0xFF; 32]));
+        assert_eq!(
+            failed_checks[0]["expected"],
+            hex::encode(get_cached_report().unwrap().body.mr_enclave.m)
+        );
+        assert_eq!(failed_checks[1]["check"], "isv_svn");
+        assert_eq!(failed_checks[1]["got"], "1");
+        assert_eq!(failed_checks[1]["expected"], ">= 2");
+        assert_eq!(parsed["advisory_ids"][0], "INTEL-SA-00334");
+        assert_eq!(
+            parsed["expected_measurements"]["mr_signer"],
+            hex::encode(get_cached_report().unwrap().body.mr_signer.m)
+        );
+
+        // the key in report_data never leaves
+        assert!(!json.contains(&hex::encode([0xAB; 32])));
+        assert_eq!(parsed["report_data"], "<redacted>");
+    }
+
+    pub fn test_verify_threshold_met() {
+        let reports = vec![
+            peer_report(1, None),
+            peer_report(2, Some([0xFF; 32])),
+            peer_report(3, None),
+        ];
+
+        let keys = verify_threshold(&reports, &VerifyPolicy::default(), 2).unwrap();
+        assert_eq!(keys, vec![[1u8; 32], [3u8; 32]]);
+    }
+
+    pub fn test_verify_threshold_not_met() {
+        let reports = vec![
+            peer_report(1, None),
+            peer_report(2, Some([0xFF; 32])),
+            peer_report(3, Some([0xFE; 32])),
+        ];
+
+        assert_eq!(
+            verify_threshold(&reports, &VerifyPolicy::default(), 2),
+            Err(NodeAuthResult::MrEnclaveMismatch)
+        );
+        assert_eq!(
+            verify_threshold(&[], &VerifyPolicy::default(), 1),
+            Err(NodeAuthResult::InvalidInput)
+        );
+    }
+
+    pub fn test_verify_batch_duplicate_key() {
+        let reports = vec![
+            peer_report(1, None),
+            peer_report(2, None),
+            peer_report(1, None),
+        ];
+        let mut seen = SeenKeys::new();
+
+        let results = verify_batch(&reports, &VerifyPolicy::default(), &mut seen);
+        assert_eq!(
+            results,
+            vec![
+                Ok([1u8; 32]),
+                Ok([2u8; 32]),
+                Err(NodeAuthResult::DuplicateKey)
+            ]
+        );
+
+        // keys are remembered across batches
+        assert!(seen.contains(&[2u8; 32]));
+        let results = verify_batch(&[peer_report(2, None)], &VerifyPolicy::default(), &mut seen);
+        assert_eq!(results, vec![Err(NodeAuthResult::DuplicateKey)]);
+    }
+
+    pub fn test_verify_batch_mixed() {
+        use crate::registration::attestation::{clear_cached_report, SELF_REPORT_CREATIONS};
+        use std::sync::atomic::Ordering;
+
+        let mut reports = vec![
+            peer_report(1, None),
+            peer_report(2, Some([0xaa; 32])),
+            peer_report(3, None),
+            peer_report(4, None),
+        ];
+        for report in reports.iter_mut() {
+            report.isv_svn = 2;
+        }
+        reports[2].isv_svn = 1;
+        let policy = VerifyPolicy {
+            min_isv_svn: 2,
+            ..Default::default()
         };
 
-        let tcb_eval_data_number = attn_report["tcbEvaluationDataNumber"]
-            .as_u64()
-            .ok_or(Error::ReportParseError)? as u16;
+        clear_cached_report();
+        let creations = SELF_REPORT_CREATIONS.load(Ordering::SeqCst);
 
-        let timestamp_str = attn_report["timestamp"]
-            .as_str()
-            .ok_or(Error::ReportParseError)?;
+        let results = verify_batch(&reports, &policy, &mut SeenKeys::new());
+        assert_eq!(
+            results,
+            vec![
+                Ok([1u8; 32]),
+                Err(NodeAuthResult::MrEnclaveMismatch),
+                Err(NodeAuthResult::IsvSvnTooLow),
+                Ok([4u8; 32]),
+            ]
+        );
 
-        let timestamp_rfc = format!("{}Z", timestamp_str);
-        let time = chrono::DateTime::parse_from_rfc3339(&timestamp_rfc).map_err(|e| {
-            warn!("Failed to decode timestamp: {}", e);
-            Error::ReportParseError
-        })?;
-        let timestamp_since_epoch = time.timestamp();
+        // the self report is fetched once for the whole batch
+        assert_eq!(SELF_REPORT_CREATIONS.load(Ordering::SeqCst), creations + 1);
+    }
 
-        // We don't actually validate the public key, since we use ephemeral certificates,
-        // and all we really care about that the report is valid and the key that is saved in the
-        // report_data field
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_normalize_advisory_id() {
+        assert_eq!(normalize_advisory_id("INTEL-SA-334"), "INTEL-SA-00334");
+        assert_eq!(normalize_advisory_id("INTEL-SA-00334"), "INTEL-SA-00334");
+        assert_eq!(normalize_advisory_id("INTEL-SA-000334"), "INTEL-SA-00334");
+        assert_eq!(normalize_advisory_id("INTEL-SA-XYZ"), "INTEL-SA-XYZ");
 
-        Ok(Self {
-            timestamp: timestamp_since_epoch as u64,
-            sgx_quote_status,
-            sgx_quote_body,
-            platform_info_blob,
-            advisory_ids: AdvisoryIDs(advisories),
-            tcb_eval_data_number,
-        })
+        let advisories = AdvisoryIDs(vec![
+            "INTEL-SA-334".to_string(),
+            "INTEL-SA-00334".to_string(),
+            "INTEL-SA-161".to_string(),
+        ]);
+        assert_eq!(
+            advisories.vulnerable(),
+            vec![
+                "INTEL-SA-161".to_string(),
+                "You must disable hyperthreading in the BIOS".to_string()
+            ]
+        );
     }
-}
 
-#[cfg(feature = "test")]
-pub mod tests {
-    use serde_json::json;
-    use std::io::Read;
-    use std::untrusted::fs::File;
+    /// Production builds don't whitelist `DEV_ONLY_ADVISORIES`, so there the
+    /// same report is vulnerable
+    #[cfg(all(feature = "SGX_MODE_HW", not(feature = "production")))]
+    pub fn test_dev_only_advisories() {
+        for advisory in BASE_WHITELISTED_ADVISORIES
+            .iter()
+            .chain(DEV_ONLY_ADVISORIES.iter())
+        {
+            assert!(AdvisoryWhitelist::compiled().contains(advisory));
+            assert!(!AdvisoryIDs(vec![advisory.to_string()]).is_vulnerable());
+        }
+    }
 
-    use crate::registration::attestation::verify_quote_ecdsa;
+    pub fn test_describe_advisory() {
+        for advisory in BASE_WHITELISTED_ADVISORIES
+            .iter()
+            .chain(DEV_ONLY_ADVISORIES.iter())
+        {
+            assert!(
+                describe(advisory).is_some(),
+                "{} has no description",
+                advisory
+            );
+        }
 
-    use super::*;
+        assert_eq!(
+            describe("INTEL-SA-161"),
+            Some("You must disable hyperthreading in the BIOS")
+        );
+        assert_eq!(describe("INTEL-SA-99999"), None);
+    }
 
-    fn tls_ra_cert_der_test() -> Vec<u8> {
-        let mut cert = vec![];
-        let mut f =
-            File::open("../execute/src/registration/fixtures/attestation_cert_hw_invalid_test.der")
-                .unwrap();
-        f.read_to_end(&mut cert).unwrap();
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_attestation_summary() {
+        let mut report = attesation_report();
+        report["version"] = json!(5);
+        report["tcbEvaluationDataNumber"] = json!(16);
 
-        cert
+        let attestation = AttestationReport::from_report_json(&report).unwrap();
+        assert_eq!(attestation.summary(), "GROUP_OUT_OF_DATE");
+
+        // status names are the ones parsed from reports
+        for status in [
+            SgxQuoteStatus::OK,
+            SgxQuoteStatus::SignatureInvalid,
+            SgxQuoteStatus::GroupRevoked,
+            SgxQuoteStatus::SignatureRevoked,
+            SgxQuoteStatus::KeyRevoked,
+            SgxQuoteStatus::SigrlVersionMismatch,
+            SgxQuoteStatus::GroupOutOfDate,
+            SgxQuoteStatus::OutOfDate,
+            SgxQuoteStatus::OutOfDateConfigurationNeeded,
+            SgxQuoteStatus::ConfigurationNeeded,
+            SgxQuoteStatus::SwHardeningNeeded,
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded,
+            SgxQuoteStatus::UnknownBadStatus,
+        ] {
+            assert_eq!(SgxQuoteStatus::from(status.as_str()), status);
+        }
+
+        report["advisoryIDs"] = json!(["INTEL-SA-00161", "INTEL-SA-00999"]);
+        let attestation = AttestationReport::from_report_json(&report).unwrap();
+        assert_eq!(
+            attestation.summary(),
+            "GROUP_OUT_OF_DATE (advisories: INTEL-SA-00161 - You must disable hyperthreading in \
+             the BIOS, INTEL-SA-00999)"
+        );
     }
 
-    fn tls_ra_cert_der_v3() -> Vec<u8> {
-        let mut cert = vec![];
-        let mut f = File::open("../execute/src/registration/fixtures/tls_ra_cert_v3.der").unwrap();
-        f.read_to_end(&mut cert).unwrap();
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_check_acceptable() {
+        let attestation = |status: &str, advisories: &[&str]| {
+            let mut report = attesation_report();
+            report["version"] = json!(5);
+            report["tcbEvaluationDataNumber"] = json!(16);
+            report["isvEnclaveQuoteStatus"] = json!(status);
+            report["advisoryIDs"] = json!(advisories);
+            AttestationReport::from_report_json(&report).unwrap()
+        };
+        let sw_hardening = AdvisoryWhitelist::from(vec!["INTEL-SA-00334".to_string()]);
+        let out_of_date = AdvisoryWhitelist::from(vec!["INTEL-SA-00161".to_string()]);
+        let lvi: &[&str] = &["INTEL-SA-00334"];
+        let l1tf: &[&str] = &["INTEL-SA-00161"];
+        let both: &[&str] = &["INTEL-SA-00334", "INTEL-SA-00161"];
 
-        cert
+        for advisories in [lvi, l1tf, both] {
+            assert_eq!(
+                attestation("OK", advisories).check_acceptable(&sw_hardening, &out_of_date),
+                Ok(())
+            );
+        }
+
+        // each status is checked against its own whitelist
+        for (status, accepted, rejected, error) in [
+            (
+                "SW_HARDENING_NEEDED",
+                lvi,
+                l1tf,
+                NodeAuthResult::BadQuoteStatus,
+            ),
+            (
+                "GROUP_OUT_OF_DATE",
+                l1tf,
+                lvi,
+                NodeAuthResult::GroupOutOfDate,
+            ),
+            (
+                "CONFIGURATION_NEEDED",
+                l1tf,
+                lvi,
+                NodeAuthResult::ConfigurationNeeded,
+            ),
+        ] {
+            assert_eq!(
+                attestation(status, &[]).check_acceptable(&sw_hardening, &out_of_date),
+                Ok(())
+            );
+            assert_eq!(
+                attestation(status, accepted).check_acceptable(&sw_hardening, &out_of_date),
+                Ok(())
+            );
+            assert_eq!(
+                attestation(status, rejected)
+                    .check_acceptable(&sw_hardening, &out_of_date)
+                    .as_ref(),
+                Err(&error)
+            );
+            assert_eq!(
+                attestation(status, both)
+                    .check_acceptable(&sw_hardening, &out_of_date)
+                    .as_ref(),
+                Err(&error)
+            );
+        }
+
+        for (status, error) in [
+            (
+                "CONFIGURATION_AND_SW_HARDENING_NEEDED",
+                NodeAuthResult::SwHardeningAndConfigurationNeeded,
+            ),
+            ("GROUP_REVOKED", NodeAuthResult::GroupRevoked),
+            ("SIGNATURE_INVALID", NodeAuthResult::BadQuoteStatus),
+        ] {
+            assert_eq!(
+                attestation(status, &[]).check_acceptable(&sw_hardening, &out_of_date),
+                Err(error)
+            );
+        }
     }
 
-    fn tls_ra_cert_der_v4() -> Vec<u8> {
-        let mut cert = vec![];
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_is_vulnerable() {
+        assert!(!AdvisoryIDs(vec![]).is_vulnerable());
+
+        let whitelisted = AdvisoryIDs(vec![
+            "INTEL-SA-00334".to_string(),
+            "INTEL-SA-219".to_string(),
+        ]);
+        assert!(!whitelisted.is_vulnerable());
+        assert!(whitelisted.vulnerable().is_empty());
+
+        let vulnerable = AdvisoryIDs(vec![
+            "INTEL-SA-00334".to_string(),
+            "INTEL-SA-00161".to_string(),
+        ]);
+        assert!(vulnerable.is_vulnerable());
+        assert!(!vulnerable.vulnerable().is_empty());
+
+        assert!(advisory_ids_match("INTEL-SA-334", "INTEL-SA-00334"));
+        assert!(!advisory_ids_match("INTEL-SA-00334", "INTEL-SA-00335"));
+        assert!(!advisory_ids_match("INTEL-SA-00334", "AMD-SA-00334"));
+        assert!(advisory_ids_match("INTEL-SA-XYZ", "INTEL-SA-XYZ"));
+    }
+
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_vulnerable_against() {
+        let advisories = AdvisoryIDs(vec![
+            "INTEL-SA-00161".to_string(),
+            "INTEL-SA-00999".to_string(),
+        ]);
+
+        let whitelist = AdvisoryWhitelist::from(vec![]);
+        assert_eq!(
+            advisories.vulnerable_against(&whitelist),
+            vec![
+                "INTEL-SA-00161".to_string(),
+                "You must disable hyperthreading in the BIOS".to_string(),
+                "INTEL-SA-00999".to_string(),
+            ]
+        );
+
+        // whitelisting an advisory removes it from the vulnerable set
+        let whitelist = AdvisoryWhitelist::from(vec!["INTEL-SA-999".to_string()]);
+        assert_eq!(
+            advisories.vulnerable_against(&whitelist),
+            vec![
+                "INTEL-SA-00161".to_string(),
+                "You must disable hyperthreading in the BIOS".to_string(),
+            ]
+        );
+
+        // the compiled whitelist is used by default
+        let advisories = AdvisoryIDs(vec!["INTEL-SA-00334".to_string()]);
+        assert!(advisories.vulnerable().is_empty());
+        assert_eq!(
+            advisories.vulnerable_against(&AdvisoryWhitelist::default()),
+            vec![
+                "INTEL-SA-00334".to_string(),
+                describe("INTEL-SA-00334").unwrap().to_string(),
+            ]
+        );
+    }
+
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_vulnerable_against_prefix() {
+        let advisories = AdvisoryIDs(vec![
+            "INTEL-SA-00615".to_string(),
+            "INTEL-SA-657".to_string(),
+            "INTEL-SA-00767".to_string(),
+            "INTEL-SA-00334".to_string(),
+            "INTEL-SA-00161".to_string(),
+        ]);
+
+        let whitelist = AdvisoryWhitelist::from(vec![
+            "INTEL-SA-006*".to_string(),
+            "INTEL-SA-334".to_string(),
+        ]);
+        assert_eq!(
+            advisories.vulnerable_against(&whitelist),
+            vec![
+                "INTEL-SA-00767".to_string(),
+                describe("INTEL-SA-00767").unwrap().to_string(),
+                "INTEL-SA-00161".to_string(),
+                describe("INTEL-SA-00161").unwrap().to_string(),
+            ]
+        );
+
+        // an exact entry doesn't match by prefix
+        let whitelist = AdvisoryWhitelist::from(vec!["INTEL-SA-006".to_string()]);
+        assert_eq!(
+            AdvisoryIDs(vec!["INTEL-SA-00615".to_string()]).vulnerable_against(&whitelist),
+            vec![
+                "INTEL-SA-00615".to_string(),
+                describe("INTEL-SA-00615").unwrap().to_string(),
+            ]
+        );
+
+        // a bare wildcard doesn't whitelist everything
+        let whitelist = AdvisoryWhitelist::from(vec!["*".to_string()]);
+        assert_eq!(
+            AdvisoryIDs(vec!["INTEL-SA-00999".to_string()]).vulnerable_against(&whitelist),
+            vec!["INTEL-SA-00999".to_string()]
+        );
+    }
+
+    #[cfg(feature = "SGX_MODE_HW")]
+    pub fn test_vulnerable_deduplicated() {
+        let advisories = AdvisoryIDs(vec![
+            "INTEL-SA-00999".to_string(),
+            "INTEL-SA-999".to_string(),
+            "INTEL-SA-00999".to_string(),
+        ]);
+        assert_eq!(
+            advisories.vulnerable_against(&AdvisoryWhitelist::default()),
+            vec!["INTEL-SA-00999".to_string()]
+        );
+    }
+
+    pub fn test_vulnerable_detailed() {
+        let advisories = AdvisoryIDs(vec![
+            "INTEL-SA-161".to_string(),
+            "INTEL-SA-00334".to_string(),
+            "INTEL-SA-00999".to_string(),
+        ]);
+
+        assert_eq!(
+            advisories.vulnerable_detailed(),
+            vec![
+                Advisory {
+                    id: "INTEL-SA-161".to_string(),
+                    description: Some("You must disable hyperthreading in the BIOS".to_string()),
+                    severity: AdvisorySeverity::High,
+                },
+                Advisory {
+                    id: "INTEL-SA-00999".to_string(),
+                    description: None,
+                    severity: AdvisorySeverity::Unknown,
+                },
+            ]
+        );
+
+        // the flat list is the same advisories, with descriptions interleaved
+        assert_eq!(
+            advisories.vulnerable_against(&AdvisoryWhitelist::compiled()),
+            vec![
+                "INTEL-SA-161".to_string(),
+                "You must disable hyperthreading in the BIOS".to_string(),
+                "INTEL-SA-00999".to_string(),
+            ]
+        );
+    }
+
+    pub fn test_attestation_report_config_and_sw_hardening() {
+        let mut report = attesation_report();
+        report["version"] = json!(5);
+        report["tcbEvaluationDataNumber"] = json!(16);
+        report["isvEnclaveQuoteStatus"] = json!("CONFIGURATION_AND_SW_HARDENING_NEEDED");
+        report["advisoryIDs"] = json!(["INTEL-SA-00334", "INTEL-SA-00615"]);
+
+        let attestation = AttestationReport::from_report_json(&report).unwrap();
+        assert_eq!(
+            attestation.sgx_quote_status,
+            SgxQuoteStatus::ConfigurationAndSwHardeningNeeded
+        );
+        assert_eq!(
+            attestation.advisory_ids,
+            AdvisoryIDs(vec![
+                "INTEL-SA-00334".to_string(),
+                "INTEL-SA-00615".to_string()
+            ])
+        );
+        assert_eq!(attestation.tcb_eval_data_number, 16);
+    }
+
+    pub fn test_attestation_report_v5() {
+        let mut raw = vec![];
         let mut f =
-            File::open("../execute/src/registration/fixtures/attestation_cert_out_of_date.der")
-                .unwrap();
-        f.read_to_end(&mut cert).unwrap();
+            File::open("../execute/src/registration/fixtures/attestation_report_v5.json").unwrap();
+        f.read_to_end(&mut raw).unwrap();
+        let report: Value = serde_json::from_slice(&raw).unwrap();
+
+        // there's no certificate with a v5 report signed by IAS, so the report is
+        // parsed as from_cert does once the signature is verified
+        let attestation = AttestationReport::from_report_json(&report).unwrap();
+        assert_eq!(
+            attestation.sgx_quote_status,
+            SgxQuoteStatus::SwHardeningNeeded
+        );
+        assert_eq!(attestation.tcb_eval_data_number, 16);
+        assert_eq!(
+            attestation.advisory_ids,
+            AdvisoryIDs(vec![
+                "INTEL-SA-00334".to_string(),
+                "INTEL-SA-00615".to_string()
+            ])
+        );
+
+        // v5 requires the TCB evaluation number
+        let mut missing_tcb = report.clone();
+        missing_tcb
+            .as_object_mut()
+            .unwrap()
+            .remove("tcbEvaluationDataNumber");
+        assert_eq!(
+            AttestationReport::from_report_json(&missing_tcb).err(),
+            Some(Error::ReportParseError)
+        );
+
+        // v4 doesn't have it
+        let mut v4 = missing_tcb;
+        v4["version"] = json!(4);
+        let attestation = AttestationReport::from_report_json(&v4).unwrap();
+        assert_eq!(attestation.tcb_eval_data_number, 0);
+
+        // the certificates signed by IAS go through the same version check
+        let attestation = AttestationReport::from_cert(&tls_ra_cert_der_v4()).unwrap();
+        assert_eq!(attestation.tcb_eval_data_number, 0);
+
+        let mut v3 = report;
+        v3["version"] = json!(3);
+        assert_eq!(
+            AttestationReport::from_report_json(&v3).err(),
+            Some(Error::ReportParseError)
+        );
+    }
+
+    pub fn test_attestation_report_max_age() {
+        let cert = tls_ra_cert_der_v4();
+        let timestamp = AttestationReport::from_cert(&cert).unwrap().timestamp;
+
+        assert!(AttestationReport::from_cert_with_max_age(&cert, timestamp + 10, 60).is_ok());
+        assert!(AttestationReport::from_cert_with_max_age(&cert, timestamp + 60, 60).is_ok());
+        assert_eq!(
+            AttestationReport::from_cert_with_max_age(&cert, timestamp + 61, 60).err(),
+            Some(Error::AttestationExpired)
+        );
+
+        let mut report = attesation_report();
+        report["version"] = json!(5);
+        report["tcbEvaluationDataNumber"] = json!(16);
+        let timestamp = AttestationReport::from_report_json(&report)
+            .unwrap()
+            .timestamp;
+        assert!(
+            AttestationReport::from_report_json_with_max_age(&report, timestamp + 60, 60).is_ok()
+        );
+        assert_eq!(
+            AttestationReport::from_report_json_with_max_age(&report, timestamp + 61, 60).err(),
+            Some(Error::AttestationExpired)
+        );
 
-        cert
+        // a certificate can't be altered without breaking the signature of IAS,
+        // so the malformed timestamp is checked on the report it vouches for
+        report["timestamp"] = json!("11/02/2020 22:25:59");
+        assert_eq!(
+            AttestationReport::from_report_json_with_max_age(&report, timestamp, 60).err(),
+            Some(Error::ReportParseError)
+        );
     }
 
-    fn _test_aes_encrypttls_ra_cert_der_out_of_date() -> Vec<u8> {
-        let mut cert = vec![];
-        let mut f = File::open(
-            "../execute/src/registration/fixtures/attestation_cert_sw_config_needed.der",
-        )
-        .unwrap();
-        f.read_to_end(&mut cert).unwrap();
+    pub fn test_attestation_report_raw_quote_body() {
+        let report = AttestationReport::from_cert(&tls_ra_cert_der_v4()).unwrap();
 
-        cert
+        let quote = SgxQuote::parse_from(report.raw_quote_body()).unwrap();
+        assert_eq!(&quote, report.quote());
+        assert_eq!(
+            report.quote().isv_enclave_report.to_bytes()[..],
+            report.raw_quote_body()[48..48 + REPORT_BODY_LEN]
+        );
     }
 
-    fn _ias_root_ca_cert_der() -> Vec<u8> {
-        let mut cert = vec![];
-        let mut f =
-            File::open("../execute/src/registration/fixtures/ias_root_ca_cert.der").unwrap();
-        f.read_to_end(&mut cert).unwrap();
+    pub fn test_advisory_ids_from_ias_report() {
+        let mut report = attesation_report();
+        report["advisoryIDs"] = json!(["INTEL-SA-00334", 219, null, "INTEL-SA-00615"]);
+        assert_eq!(
+            AdvisoryIDs::from_ias_report(&report),
+            AdvisoryIDs(vec![
+                "INTEL-SA-00334".to_string(),
+                "INTEL-SA-00615".to_string()
+            ])
+        );
 
-        cert
-    }
+        // repeated ids are only kept once, where they first appear
+        report["advisoryIDs"] = json!([
+            "INTEL-SA-00615",
+            "INTEL-SA-00334",
+            "INTEL-SA-00615",
+            "INTEL-SA-00334"
+        ]);
+        assert_eq!(
+            AdvisoryIDs::from_ias_report(&report),
+            AdvisoryIDs(vec![
+                "INTEL-SA-00615".to_string(),
+                "INTEL-SA-00334".to_string()
+            ])
+        );
 
-    fn attesation_report() -> Value {
-        let report = json!({
-            "version": 3,
-            "timestamp": "2020-02-11T22:25:59.682915",
-            "platformInfoBlob": "1502006504000900000D0D02040180030000000000000000000\
-                                 A00000B000000020000000000000B2FE0AE0F7FD4D552BF7EF4\
-                                 C938D44E349F1BD0E76F041362DC52B43B7B25994978D792137\
-                                 90362F6DAE91797ACF5BD5072E45F9A60795D1FFB10140421D8\
-                                 691FFD",
-            "isvEnclaveQuoteStatus": "GROUP_OUT_OF_DATE",
-            "isvEnclaveQuoteBody": "AgABAC8LAAAKAAkAAAAAAK1zRQOIpndiP4IhlnW2AkwAAAAA\
-                                    AAAAAAAAAAAAAAAABQ4CBf+AAAAAAAAAAAAAAAAAAAAAAAAA\
-                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAABwAAAAAAAAAHAAAA\
-                                    AAAAADMKqRCjd2eA4gAmrj2sB68OWpMfhPH4MH27hZAvWGlT\
-                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAACD1xnn\
-                                    ferKFHD2uvYqTXdDA8iZ22kCD5xw7h38CMfOngAAAAAAAAAA\
-                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
-                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
-                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
-                                    AAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAA\
-                                    AAAAAAAAAADYIY9k0MVmCdIDUuFLf/2bGIHAfPjO9nvC7fgz\
-                                    rQedeA3WW4dFeI6oe+RCLdV3XYD1n6lEZjITOzPPLWDxulGz",
-            "id": "53530608302195762335736519878284384788",
-            "epidPseudonym": "NRksaQej8R/SyyHpZXzQGNBXqfrzPy5KCxcmJrEjupXrq3xrm2y2+J\
-                              p0IBVtcW15MCekYs9K3UH82fPyj6F5ciJoMsgEMEIvRR+csX9uyd54\
-                              p+m+/RVyuGYhWbhUcpJigdI5Q3x04GG/A7EP10j/zypwqhYLQh0qN1\
-                              ykYt1N1P0="
-        });
+        report["advisoryIDs"] = json!([]);
+        assert_eq!(AdvisoryIDs::from_ias_report(&report), AdvisoryIDs(vec![]));
 
-        report
+        report["advisoryIDs"] = Value::Null;
+        assert_eq!(AdvisoryIDs::from_ias_report(&report), AdvisoryIDs(vec![]));
+
+        let report = attesation_report();
+        assert!(report.get("advisoryIDs").is_none());
+        assert_eq!(AdvisoryIDs::from_ias_report(&report), AdvisoryIDs(vec![]));
     }
 
-    pub fn test_sgx_quote_parse_from() {
-        let attn_report = attesation_report();
-        let sgx_quote_body_encoded = attn_report["isvEnclaveQuoteBody"].as_str().unwrap();
-        let quote_raw = base64::decode(&sgx_quote_body_encoded.as_bytes()).unwrap();
-        let sgx_quote = SgxQuote::parse_from(quote_raw.as_slice()).unwrap();
+    pub fn test_advisory_ids_from_str() {
+        let expected = || {
+            AdvisoryIDs(vec![
+                "INTEL-SA-00334".to_string(),
+                "INTEL-SA-00219".to_string(),
+            ])
+        };
 
         assert_eq!(
-            sgx_quote.version,
-            SgxQuoteVersion::V2(SgxEpidQuoteSigType::Linkable)
+            "INTEL-SA-00334,INTEL-SA-00219".parse::<AdvisoryIDs>(),
+            Ok(expected())
         );
-        assert_eq!(sgx_quote.gid, 2863);
-        assert_eq!(sgx_quote.isv_svn_qe, 10);
-        assert_eq!(sgx_quote.isv_svn_pce, 9);
         assert_eq!(
-            sgx_quote.qe_vendor_id,
-            Uuid::parse_str("00000000-ad73-4503-88a6-77623f822196").unwrap()
+            " INTEL-SA-00334 ,\tINTEL-SA-00219\n".parse::<AdvisoryIDs>(),
+            Ok(expected())
         );
         assert_eq!(
-            sgx_quote.user_data,
-            [117, 182, 2, 76, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+            "INTEL-SA-00334,,INTEL-SA-00219,".parse::<AdvisoryIDs>(),
+            Ok(expected())
+        );
+        assert_eq!(
+            "INTEL-SA-00334,INTEL-SA-00219,INTEL-SA-00334".parse::<AdvisoryIDs>(),
+            Ok(expected())
         );
+        assert_eq!("".parse::<AdvisoryIDs>(), Ok(AdvisoryIDs(vec![])));
+        assert_eq!(" , ".parse::<AdvisoryIDs>(), Ok(AdvisoryIDs(vec![])));
+    }
 
-        let isv_enclave_report = sgx_quote.isv_enclave_report;
+    pub fn test_attestation_report_advisories() {
+        let report = AttestationReport::from_cert(&tls_ra_cert_der_v4()).unwrap();
+
+        assert_eq!(report.sgx_quote_status, SgxQuoteStatus::GroupOutOfDate);
         assert_eq!(
-            isv_enclave_report.cpu_svn,
-            [5, 14, 2, 5, 255, 128, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+            report.advisories(),
+            &AdvisoryIDs(vec![
+                "INTEL-SA-00161".to_string(),
+                "INTEL-SA-00219".to_string(),
+                "INTEL-SA-00289".to_string(),
+                "INTEL-SA-00320".to_string(),
+                "INTEL-SA-00329".to_string(),
+            ])
         );
-        assert_eq!(isv_enclave_report.misc_select, 0);
+    }
+
+    fn peer_attestation(report: SgxEnclaveReport) -> AttestationReport {
+        let attn_report = attesation_report();
+        let quote_raw =
+            base64::decode(attn_report["isvEnclaveQuoteBody"].as_str().unwrap()).unwrap();
+        let mut sgx_quote_body = SgxQuote::parse_from(quote_raw.as_slice()).unwrap();
+        let mut sgx_quote_raw = quote_raw;
+        sgx_quote_raw.splice(48..48 + REPORT_BODY_LEN, report.to_bytes());
+        sgx_quote_body.isv_enclave_report = report;
+
+        AttestationReport {
+            timestamp: 0,
+            sgx_quote_status: SgxQuoteStatus::OK,
+            sgx_quote_body,
+            platform_info_blob: None,
+            advisory_ids: AdvisoryIDs(vec![]),
+            tcb_eval_data_number: 0,
+            sgx_quote_raw,
+        }
+    }
+
+    pub fn test_verify_with_hook() {
+        let report = peer_report(1, None);
+        let attestation = peer_attestation(peer_report(1, None));
+        let policy = VerifyPolicy::default();
+
         assert_eq!(
-            isv_enclave_report.attributes,
-            [7, 0, 0, 0, 0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]
+            verify_with_hook(&report, &attestation, &policy, |_, _| Ok(())),
+            Ok(())
         );
+
+        // standard checks pass, but the hook rejects the report
         assert_eq!(
-            isv_enclave_report.mr_enclave,
-            [
-                51, 10, 169, 16, 163, 119, 103, 128, 226, 0, 38, 174, 61, 172, 7, 175, 14, 90, 147,
-                31, 132, 241, 248, 48, 125, 187, 133, 144, 47, 88, 105, 83
-            ]
+            verify_with_hook(&report, &attestation, &policy, |_, attestation| {
+                if attestation.advisory_ids.0.is_empty() {
+                    Err(NodeAuthResult::BadQuoteStatus)
+                } else {
+                    Ok(())
+                }
+            }),
+            Err(NodeAuthResult::BadQuoteStatus)
         );
+
+        // the hook isn't reached when standard checks fail
+        let bad_report = peer_report(1, Some([0xFF; 32]));
         assert_eq!(
-            isv_enclave_report.mr_signer,
-            [
-                131, 215, 25, 231, 125, 234, 202, 20, 112, 246, 186, 246, 42, 77, 119, 67, 3, 200,
-                153, 219, 105, 2, 15, 156, 112, 238, 29, 252, 8, 199, 206, 158
-            ]
+            verify_with_hook(&bad_report, &attestation, &policy, |_, _| panic!(
+                "hook called for a report that failed standard checks"
+            )),
+            Err(NodeAuthResult::MrEnclaveMismatch)
         );
-        assert_eq!(isv_enclave_report.isv_prod_id, 0);
-        assert_eq!(isv_enclave_report.isv_svn, 0);
+
+        // nor when the attestation vouches for another report
+        let other_attestation = peer_attestation(peer_report(2, None));
         assert_eq!(
-            isv_enclave_report.report_data.to_vec(),
-            [
-                216, 33, 143, 100, 208, 197, 102, 9, 210, 3, 82, 225, 75, 127, 253, 155, 24, 129,
-                192, 124, 248, 206, 246, 123, 194, 237, 248, 51, 173, 7, 157, 120, 13, 214, 91,
-                135, 69, 120, 142, 168, 123, 228, 66, 45, 213, 119, 93, 128, 245, 159, 169, 68,
-                102, 50, 19, 59, 51, 207, 45, 96, 241, 186, 81, 179
-            ]
-            .to_vec()
+            verify_with_hook(&report, &other_attestation, &policy, |_, _| panic!(
+                "hook called for a report the attestation isn't for"
+            )),
+            Err(NodeAuthResult::ReportDataMismatch)
         );
     }
 
@@ -927,6 +6164,258 @@ pub mod tests {
         assert!(report.is_err());
     }
 
+    struct FixedClock(u64);
+
+    impl Clock for FixedClock {
+        fn now(&self) -> u64 {
+            self.0
+        }
+    }
+
+    // the fixture is GROUP_OUT_OF_DATE, which production builds reject
+    pub fn test_attestation_verify_full() {
+        let cert = tls_ra_cert_der_v4();
+        let report = AttestationReport::from_cert(&cert).unwrap();
+        let mr_enclave = report.sgx_quote_body.isv_enclave_report.mr_enclave;
+        let attributes = report.sgx_quote_body.isv_enclave_report.attributes;
+
+        let policy = VerifyPolicy {
+            mr_enclave: Some(Box::new(Exact(mr_enclave))),
+            attributes: Some(attributes),
+            max_report_age: Some(60),
+            ..Default::default()
+        };
+
+        let node = AttestationReport::verify_full(
+            &AttestationEvidence::Ias(&cert),
+            &policy,
+            &FixedClock(report.timestamp + 10),
+        )
+        .unwrap();
+        assert_eq!(
+            node.public_key,
+            report
+                .sgx_quote_body
+                .isv_enclave_report
+                .get_owner_key()
+                .unwrap()
+        );
+
+        // stale report
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Ias(&cert),
+                &policy,
+                &FixedClock(report.timestamp + 61)
+            )
+            .unwrap_err(),
+            Error::VerifyFailed {
+                stage: VerifyStage::Freshness,
+                reason: NodeAuthResult::InvalidCert
+            }
+        );
+
+        // the measurement isn't accepted by the policy
+        let policy = VerifyPolicy {
+            mr_enclave: Some(Box::new(Exact([0xFF; 32]))),
+            ..Default::default()
+        };
+        assert_eq!(
+            AttestationReport::verify_full(&AttestationEvidence::Ias(&cert), &policy, &SystemClock)
+                .unwrap_err(),
+            Error::VerifyFailed {
+                stage: VerifyStage::Measurement,
+                reason: NodeAuthResult::MrEnclaveMismatch
+            }
+        );
+
+        // a certificate without a valid report
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Ias(&tls_ra_cert_der_v3()),
+                &VerifyPolicy::default(),
+                &SystemClock
+            )
+            .unwrap_err(),
+            Error::VerifyFailed {
+                stage: VerifyStage::Parse,
+                reason: NodeAuthResult::InvalidCert
+            }
+        );
+    }
+
+    pub fn test_attestation_verify_full_deadline() {
+        use std::time::Duration;
+
+        // mock of the untrusted time, which answers only after `delay`
+        struct SlowClock {
+            delay: Duration,
+            now: u64,
+        }
+
+        impl Clock for SlowClock {
+            fn now(&self) -> u64 {
+                let until = Instant::now() + self.delay;
+                while Instant::now() <= until {}
+                self.now
+            }
+        }
+
+        struct UnreachableClock;
+
+        impl Clock for UnreachableClock {
+            fn now(&self) -> u64 {
+                panic!("clock read after the deadline");
+            }
+        }
+
+        let cert = tls_ra_cert_der_v4();
+        let report = AttestationReport::from_cert(&cert).unwrap();
+        let enclave_report = &report.sgx_quote_body.isv_enclave_report;
+        let slow_clock = SlowClock {
+            delay: Duration::from_millis(20),
+            now: report.timestamp + 10,
+        };
+        let policy = |deadline: Instant| VerifyPolicy {
+            mr_enclave: Some(Box::new(Exact(enclave_report.mr_enclave))),
+            attributes: Some(enclave_report.attributes),
+            max_report_age: Some(60),
+            deadline: Some(deadline),
+            ..Default::default()
+        };
+
+        assert!(AttestationReport::verify_full(
+            &AttestationEvidence::Ias(&cert),
+            &policy(Instant::now() + Duration::from_secs(60)),
+            &slow_clock
+        )
+        .is_ok());
+
+        let deadline_exceeded = |stage| Error::VerifyFailed {
+            stage,
+            reason: NodeAuthResult::DeadlineExceeded,
+        };
+
+        // the time arrives after the deadline
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Ias(&cert),
+                &policy(Instant::now() + Duration::from_millis(10)),
+                &slow_clock
+            )
+            .unwrap_err(),
+            deadline_exceeded(VerifyStage::Freshness)
+        );
+
+        // the time isn't read once the deadline passed
+        let passed = Instant::now();
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Ias(&cert),
+                &policy(passed),
+                &UnreachableClock
+            )
+            .unwrap_err(),
+            deadline_exceeded(VerifyStage::Freshness)
+        );
+
+        // nor is a DCAP quote verified
+        let mut quote = vec![];
+        File::open("../execute/src/registration/fixtures/attestation_dcap.quote")
+            .unwrap()
+            .read_to_end(&mut quote)
+            .unwrap();
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Dcap {
+                    quote: &quote,
+                    collateral: &[],
+                },
+                &policy(passed),
+                &UnreachableClock
+            )
+            .unwrap_err(),
+            deadline_exceeded(VerifyStage::Signature)
+        );
+
+        // pure checks still complete and report their own error
+        let no_io = VerifyPolicy {
+            mr_enclave: Some(Box::new(Exact([0xFF; 32]))),
+            deadline: Some(passed),
+            ..Default::default()
+        };
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Ias(&cert),
+                &no_io,
+                &UnreachableClock
+            )
+            .unwrap_err(),
+            Error::VerifyFailed {
+                stage: VerifyStage::Measurement,
+                reason: NodeAuthResult::MrEnclaveMismatch
+            }
+        );
+    }
+
+    pub fn test_attestation_verify_full_dcap() {
+        let mut vec_quote = vec![];
+        File::open("../execute/src/registration/fixtures/attestation_dcap.quote")
+            .unwrap()
+            .read_to_end(&mut vec_quote)
+            .unwrap();
+        let mut vec_coll = vec![];
+        File::open("../execute/src/registration/fixtures/attestation_dcap.collateral")
+            .unwrap()
+            .read_to_end(&mut vec_coll)
+            .unwrap();
+        let time_s = 1709649832;
+
+        // the fixture is of a debug enclave, which production builds reject, and
+        // quotes can only be verified on SGX hardware
+        #[cfg(all(feature = "SGX_MODE_HW", not(feature = "production")))]
+        {
+            let body = &SgxQuote::parse_from(&vec_quote).unwrap().isv_enclave_report;
+            let policy = VerifyPolicy {
+                mr_enclave: Some(Box::new(Exact(body.mr_enclave))),
+                attributes: Some(body.attributes),
+                ..Default::default()
+            };
+
+            let node = AttestationReport::verify_full(
+                &AttestationEvidence::Dcap {
+                    quote: &vec_quote,
+                    collateral: &vec_coll,
+                },
+                &policy,
+                &FixedClock(time_s),
+            )
+            .unwrap();
+            assert_eq!(node.public_key, body.get_owner_key().unwrap());
+            assert_eq!(node.attestation.timestamp, time_s);
+        }
+
+        // flip a bit of the report data, which the quote signature covers
+        let report_data_offset = 48 + 320;
+        vec_quote[report_data_offset] ^= 1;
+
+        assert_eq!(
+            AttestationReport::verify_full(
+                &AttestationEvidence::Dcap {
+                    quote: &vec_quote,
+                    collateral: &vec_coll,
+                },
+                &VerifyPolicy::default(),
+                &FixedClock(time_s)
+            )
+            .unwrap_err(),
+            Error::VerifyFailed {
+                stage: VerifyStage::Signature,
+                reason: NodeAuthResult::InvalidCert
+            }
+        );
+    }
+
     pub fn test_attestation_report_test() {
         let tls_ra_cert = tls_ra_cert_der_test();
         let report = AttestationReport::from_cert(&tls_ra_cert);
@@ -957,6 +6446,184 @@ pub mod tests {
         (vec_quote, vec_coll, 1709649832)
     }
 
+    pub fn test_take_bytes() {
+        let bytes = [1u8, 2, 3];
+        let mut pos = 1;
+
+        assert_eq!(take_bytes(&bytes, &mut pos, 0), Ok(&[][..]));
+        assert_eq!(pos, 1);
+        assert_eq!(take_bytes(&bytes, &mut pos, 2), Ok(&[2u8, 3][..]));
+        assert_eq!(pos, 3);
+        assert_eq!(take_bytes(&bytes, &mut pos, 0), Ok(&[][..]));
+
+        assert_eq!(
+            take_bytes(&bytes, &mut pos, 1),
+            Err(Error::ReportTruncated {
+                expected: 4,
+                got: 3
+            })
+        );
+        assert_eq!(pos, 3);
+        assert!(take_bytes(&bytes, &mut pos, usize::MAX).is_err());
+    }
+
+    pub fn test_sgx_quote_parse_v3() {
+        let mut vec_quote = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/attestation_dcap.quote").unwrap();
+        f.read_to_end(&mut vec_quote).unwrap();
+
+        let quote = SgxQuote::parse_from(&vec_quote).unwrap();
+        assert_eq!(
+            quote.version,
+            SgxQuoteVersion::V3(SgxEcdsaQuoteAkType::P256_256)
+        );
+
+        // the ISV enclave report is parsed the same way as for EPID quotes
+        let isv_enclave_report = SgxEnclaveReport::parse_from(&vec_quote[48..432]).unwrap();
+        assert_eq!(
+            quote.isv_enclave_report.mr_enclave,
+            isv_enclave_report.mr_enclave
+        );
+        assert_eq!(
+            quote.isv_enclave_report.mr_signer,
+            isv_enclave_report.mr_signer
+        );
+        assert_eq!(
+            quote.isv_enclave_report.report_data[..],
+            isv_enclave_report.report_data[..]
+        );
+
+        // signature data starts at 436, QE report is 128 bytes into it
+        let qe_report = quote.qe_report().unwrap();
+        let expected = SgxEnclaveReport::parse_from(&vec_quote[564..948]).unwrap();
+        assert_eq!(qe_report.mr_enclave, expected.mr_enclave);
+        assert_eq!(qe_report.mr_signer, expected.mr_signer);
+        assert_eq!(qe_report.isv_svn, expected.isv_svn);
+        assert_ne!(qe_report.mr_enclave, quote.isv_enclave_report.mr_enclave);
+
+        // header fields of the ECDSA quote
+        assert_eq!(quote.att_key_type(), Some(SgxEcdsaQuoteAkType::P256_256));
+        assert_eq!(
+            quote.qe_vendor_id.to_string(),
+            "939a7233-f79c-4ca9-940a-0db3957f0607"
+        );
+        assert!(quote.is_intel_qe());
+        let mut other_vendor = vec_quote.clone();
+        other_vendor[12] ^= 0xff;
+        assert!(!SgxQuote::parse_from(&other_vendor).unwrap().is_intel_qe());
+        assert_eq!(quote.isv_svn_qe, 10);
+        assert_eq!(quote.isv_svn_pce, 15);
+        assert_eq!(quote.user_data[..], vec_quote[28..48]);
+        assert_eq!(quote.signature_data(), Some(436..vec_quote.len()));
+
+        // certification data follows the QE report signature and the 32 bytes
+        // of QE authentication data
+        assert_eq!(quote.cert_data_type(), Some(5));
+        let cert_data = quote.certification_data().unwrap();
+        assert_eq!(cert_data.len(), 3552);
+        assert_eq!(cert_data, &vec_quote[vec_quote.len() - 3552..]);
+        assert!(cert_data.starts_with(b"-----BEGIN CERTIFICATE-----"));
+
+        // certification data size larger than what's left
+        let mut bad_cert_data = vec_quote.clone();
+        let cert_size_pos = vec_quote.len() - 3552 - 4;
+        bad_cert_data[cert_size_pos..cert_size_pos + 4].copy_from_slice(&3553u32.to_le_bytes());
+        assert_eq!(
+            SgxQuote::parse_from(&bad_cert_data).err(),
+            Some(Error::BadFieldLength {
+                field: "certification_data"
+            })
+        );
+
+        // unknown attestation key type
+        let mut unknown_ak_type = vec_quote.clone();
+        unknown_ak_type[2..4].copy_from_slice(&7u16.to_le_bytes());
+        assert_eq!(
+            SgxQuote::parse_from(&unknown_ak_type).err(),
+            Some(Error::ReportParseError)
+        );
+
+        // unknown quote version
+        let mut unknown_version = vec_quote.clone();
+        unknown_version[0] = 4;
+        assert_eq!(
+            SgxQuote::parse_from(&unknown_version).err(),
+            Some(Error::UnsupportedQuoteVersion(4))
+        );
+
+        // signature data shorter than declared
+        assert_eq!(
+            SgxQuote::parse_from(&vec_quote[..vec_quote.len() - 1]).err(),
+            Some(Error::ReportParseError)
+        );
+
+        // signature data longer than declared
+        let mut long_quote = vec_quote.clone();
+        long_quote.extend_from_slice(&[0u8; 16]);
+        assert_eq!(
+            SgxQuote::parse_from(&long_quote).err(),
+            Some(Error::ReportParseError)
+        );
+
+        // EPID quotes don't carry a QE report
+        let report = attesation_report();
+        let quote_raw = base64::decode(report["isvEnclaveQuoteBody"].as_str().unwrap()).unwrap();
+        assert!(SgxQuote::parse_from(&quote_raw)
+            .unwrap()
+            .qe_report()
+            .is_none());
+    }
+
+    pub fn test_verify_qe_identity() {
+        let mut vec_quote = vec![];
+        let mut f =
+            File::open("../execute/src/registration/fixtures/attestation_dcap.quote").unwrap();
+        f.read_to_end(&mut vec_quote).unwrap();
+        let quote = SgxQuote::parse_from(&vec_quote).unwrap();
+        let qe_report = quote.qe_report().unwrap();
+
+        let identity = QeIdentity {
+            mr_signer: qe_report.mr_signer,
+            isv_prod_id: qe_report.isv_prod_id,
+            min_isv_svn: qe_report.isv_svn,
+        };
+        assert_eq!(verify_qe_identity(qe_report, &identity), Ok(()));
+
+        let older = QeIdentity {
+            min_isv_svn: qe_report.isv_svn - 1,
+            ..identity.clone()
+        };
+        assert_eq!(verify_qe_identity(qe_report, &older), Ok(()));
+
+        let other_signer = QeIdentity {
+            mr_signer: [0xFF; 32],
+            ..identity.clone()
+        };
+        assert_eq!(
+            verify_qe_identity(qe_report, &other_signer),
+            Err(NodeAuthResult::MrSignerMismatch)
+        );
+
+        let other_product = QeIdentity {
+            isv_prod_id: qe_report.isv_prod_id + 1,
+            ..identity.clone()
+        };
+        assert_eq!(
+            verify_qe_identity(qe_report, &other_product),
+            Err(NodeAuthResult::IsvProdIdMismatch)
+        );
+
+        let newer = QeIdentity {
+            min_isv_svn: qe_report.isv_svn + 1,
+            ..identity
+        };
+        assert_eq!(
+            verify_qe_identity(qe_report, &newer),
+            Err(NodeAuthResult::IsvSvnTooLow)
+        );
+    }
+
     pub fn test_attestation_dcap() {
         let (vec_quote, vec_coll, time_s) = load_attestation_dcap();
 
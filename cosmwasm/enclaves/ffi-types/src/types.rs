@@ -175,54 +175,170 @@ pub enum EnclaveError {
 
 /// This type represents the possible error conditions that can be encountered in the
 /// enclave while authenticating a new node in the network.
+/// The values cross the FFI, so they are explicit and must never change: new
+/// variants are appended with the next free value, and to `NodeAuthResult::ALL`.
 /// cbindgen:prefix-with-name
 #[repr(C)]
-#[derive(Debug, Display, PartialEq, Eq)]
+#[derive(Debug, Display, Clone, Copy, PartialEq, Eq)]
 pub enum NodeAuthResult {
     #[display(fmt = "Enclave quote is valid")]
-    Success,
+    Success = 0,
     #[display(fmt = "Enclave quote status was GROUP_OUT_OF_DATE which is not allowed")]
-    GroupOutOfDate,
+    GroupOutOfDate = 1,
     #[display(fmt = "Enclave quote status was SIGNATURE_INVALID which is not allowed")]
-    SignatureInvalid,
+    SignatureInvalid = 2,
     #[display(fmt = "Enclave quote status was SIGNATURE_REVOKED which is not allowed")]
-    SignatureRevoked,
+    SignatureRevoked = 3,
     #[display(fmt = "Enclave quote status was GROUP_REVOKED which is not allowed")]
-    GroupRevoked,
+    GroupRevoked = 4,
     #[display(fmt = "Enclave quote status was KEY_REVOKED which is not allowed")]
-    KeyRevoked,
+    KeyRevoked = 5,
     #[display(fmt = "Enclave quote status was SIGRL_VERSION_MISMATCH which is not allowed")]
-    SigrlVersionMismatch,
+    SigrlVersionMismatch = 6,
     #[display(fmt = "Enclave quote status was CONFIGURATION_NEEDED which is not allowed")]
-    ConfigurationNeeded,
+    ConfigurationNeeded = 7,
     #[display(
         fmt = "Enclave quote status was CONFIGURATION_AND_SW_HARDENING_NEEDED which is not allowed"
     )]
-    SwHardeningAndConfigurationNeeded,
+    SwHardeningAndConfigurationNeeded = 8,
     #[display(fmt = "Enclave quote status invalid")]
-    BadQuoteStatus,
+    BadQuoteStatus = 9,
     #[display(fmt = "Enclave version mismatch. Registering enclave had different code signature")]
-    MrEnclaveMismatch,
+    MrEnclaveMismatch = 10,
     #[display(fmt = "Enclave version mismatch. Registering enclave had different signer")]
-    MrSignerMismatch,
+    MrSignerMismatch = 11,
     #[display(fmt = "Enclave received invalid inputs")]
-    InvalidInput,
+    InvalidInput = 12,
     #[display(fmt = "The provided certificate was invalid")]
-    InvalidCert,
+    InvalidCert = 13,
     #[display(fmt = "Writing to file system from the enclave failed")]
-    CantWriteToStorage,
+    CantWriteToStorage = 14,
     #[display(fmt = "The public key in the certificate appears to be malformed")]
-    MalformedPublicKey,
+    MalformedPublicKey = 15,
     #[display(fmt = "Encrypting the seed failed")]
-    SeedEncryptionFailed,
+    SeedEncryptionFailed = 16,
     #[display(fmt = "failed to allocate minimal safety buffer")]
-    MemorySafetyAllocationError,
+    MemorySafetyAllocationError = 17,
     #[display(fmt = "Enclave quote status does not match expected status")]
-    EnclaveQuoteStatus,
+    EnclaveQuoteStatus = 18,
     #[display(
         fmt = "Unexpected panic during node authentication. Certificate may be malformed or invalid"
     )]
-    Panic,
+    Panic = 19,
+    #[display(fmt = "Enclave report was generated with an unexpected key id")]
+    KeyIdMismatch = 20,
+    #[display(fmt = "Enclave security version is lower than the minimum allowed")]
+    IsvSvnTooLow = 21,
+    #[display(fmt = "The same public key was presented by distinct nodes")]
+    DuplicateKey = 22,
+    #[display(fmt = "Enclave report data does not match the expected key or challenge")]
+    ReportDataMismatch = 23,
+    #[display(fmt = "Node authentication did not complete before its deadline")]
+    DeadlineExceeded = 24,
+    #[display(fmt = "Enclave report was not generated by a 64-bit enclave")]
+    Not64Bit = 25,
+    #[display(fmt = "Enclave report was generated by an enclave running in debug mode")]
+    DebugEnclaveNotAllowed = 26,
+    #[display(fmt = "Platform security version is too low for the enclave security version")]
+    SvnInconsistent = 27,
+    #[display(fmt = "Failed to get the report of this enclave to verify against")]
+    InvalidSelfReport = 28,
+    #[display(fmt = "Platform security version is lower than the minimum allowed")]
+    CpuSvnTooLow = 29,
+    #[display(fmt = "Enclave product id does not match the expected one")]
+    IsvProdIdMismatch = 30,
+    #[display(fmt = "Expected report data doesn't fit in the report")]
+    ReportDataOutOfRange = 31,
+    #[display(fmt = "Enclave was loaded with unexpected MISC features")]
+    MiscSelectMismatch = 32,
+    #[display(fmt = "Quoting Enclave security version is lower than the minimum allowed")]
+    QeSvnTooLow = 33,
+    #[display(fmt = "PCE security version is lower than the minimum allowed")]
+    PceSvnTooLow = 34,
+    #[display(fmt = "Enclave attributes are different than expected")]
+    AttributesMismatch = 35,
+}
+
+/// Numeric codes of `NodeAuthResult`, which are its discriminants, i.e. the values
+/// that cross the FFI. They are stable across versions: new variants take the next
+/// free code, and codes must never be reused or reassigned.
+impl NodeAuthResult {
+    /// Every variant, in the order of their codes
+    pub const ALL: &'static [NodeAuthResult] = &[
+        NodeAuthResult::Success,
+        NodeAuthResult::GroupOutOfDate,
+        NodeAuthResult::SignatureInvalid,
+        NodeAuthResult::SignatureRevoked,
+        NodeAuthResult::GroupRevoked,
+        NodeAuthResult::KeyRevoked,
+        NodeAuthResult::SigrlVersionMismatch,
+        NodeAuthResult::ConfigurationNeeded,
+        NodeAuthResult::SwHardeningAndConfigurationNeeded,
+        NodeAuthResult::BadQuoteStatus,
+        NodeAuthResult::MrEnclaveMismatch,
+        NodeAuthResult::MrSignerMismatch,
+        NodeAuthResult::InvalidInput,
+        NodeAuthResult::InvalidCert,
+        NodeAuthResult::CantWriteToStorage,
+        NodeAuthResult::MalformedPublicKey,
+        NodeAuthResult::SeedEncryptionFailed,
+        NodeAuthResult::MemorySafetyAllocationError,
+        NodeAuthResult::EnclaveQuoteStatus,
+        NodeAuthResult::Panic,
+        NodeAuthResult::KeyIdMismatch,
+        NodeAuthResult::IsvSvnTooLow,
+        NodeAuthResult::DuplicateKey,
+        NodeAuthResult::ReportDataMismatch,
+        NodeAuthResult::DeadlineExceeded,
+        NodeAuthResult::Not64Bit,
+        NodeAuthResult::DebugEnclaveNotAllowed,
+        NodeAuthResult::SvnInconsistent,
+        NodeAuthResult::InvalidSelfReport,
+        NodeAuthResult::CpuSvnTooLow,
+        NodeAuthResult::IsvProdIdMismatch,
+        NodeAuthResult::ReportDataOutOfRange,
+        NodeAuthResult::MiscSelectMismatch,
+        NodeAuthResult::QeSvnTooLow,
+        NodeAuthResult::PceSvnTooLow,
+        NodeAuthResult::AttributesMismatch,
+    ];
+
+    pub fn as_code(&self) -> u32 {
+        *self as u32
+    }
+
+    pub fn from_code(code: u32) -> Option<Self> {
+        Self::ALL.get(code as usize).copied()
+    }
+
+    /// `Ok(())` for `Success`, or the error otherwise
+    pub fn to_result(self) -> Result<(), NodeAuthResult> {
+        match self {
+            NodeAuthResult::Success => Ok(()),
+            e => Err(e),
+        }
+    }
+}
+
+// ALL is indexed by code, so it must list the codes in order, without gaps
+const _: () = {
+    let mut i = 0;
+    while i < NodeAuthResult::ALL.len() {
+        assert!(
+            NodeAuthResult::ALL[i] as usize == i,
+            "NodeAuthResult::ALL must list the variants in the order of their codes"
+        );
+        i += 1;
+    }
+};
+
+impl From<Result<(), NodeAuthResult>> for NodeAuthResult {
+    fn from(result: Result<(), NodeAuthResult>) -> Self {
+        match result {
+            Ok(()) => NodeAuthResult::Success,
+            Err(e) => e,
+        }
+    }
 }
 
 /// This type represents the possible error conditions that can be encountered in the